@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about)]
@@ -16,9 +17,105 @@ pub struct Args {
     #[arg(long = "unleash")]
     unrestricted: bool,
 
+    /// Allow execution of shell commands which start with the given words
+    #[arg(long, value_name = "pattern", conflicts_with = "unrestricted")]
+    allow: Vec<String>,
+
+    /// Lift resource limits on the lie script
+    #[arg(long)]
+    unlimited: bool,
+
+    /// Run all system commands and save their output to a cache next to the lie
+    #[arg(long)]
+    prepare: bool,
+
+    /// Replay system command output from the cache instead of running commands
+    #[arg(long, conflicts_with = "prepare")]
+    replay_cache: bool,
+
     /// Output all commands which would be run
     #[arg(long)]
     dry_run: bool,
+
+    /// Output the tags of the lie in order
+    #[arg(long, visible_alias = "outline")]
+    list_tags: bool,
+
+    /// Start telling the lie from the given tag
+    #[arg(long, value_name = "tag")]
+    from: Option<String>,
+
+    /// Stop telling the lie at the given tag
+    #[arg(long, value_name = "tag")]
+    to: Option<String>,
+
+    /// Resume from each pause automatically after a delay
+    #[arg(
+        long,
+        value_name = "millis",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1500"
+    )]
+    autoplay: Option<u64>,
+
+    /// Type commands as keys are pressed, revealing the given number of characters per key
+    #[arg(
+        long,
+        value_name = "chars",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "3",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "autoplay"
+    )]
+    typer: Option<u64>,
+
+    /// Restart the lie when it ends, after an optional delay
+    #[arg(
+        long = "loop",
+        value_name = "millis",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0"
+    )]
+    loop_wait: Option<u64>,
+
+    /// Re-execute system commands on each loop instead of replaying their output
+    #[arg(long, requires = "loop_wait")]
+    rerun_systems: bool,
+
+    /// Run system commands in the background before they are reached, showing their output
+    /// with an optional delay between lines
+    #[arg(
+        long,
+        value_name = "millis",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0",
+        conflicts_with = "replay_cache"
+    )]
+    prefetch: Option<u64>,
+
+    /// Record an asciicast to the given file instead of telling the lie
+    #[arg(long, value_name = "cast")]
+    record: Option<String>,
+
+    /// Time to wait at each pause while recording
+    #[arg(long, value_name = "millis", default_value_t = 1000)]
+    pause_delay: u64,
+
+    /// Output the final screen of the lie as text
+    #[arg(long)]
+    snapshot: bool,
+
+    /// Also output the screen at each tag when snapshotting
+    #[arg(long, requires = "snapshot")]
+    snapshot_tags: bool,
+
+    /// Terminal size to use when recording or snapshotting
+    #[arg(long, value_name = "colsxrows", value_parser = parse_size)]
+    size: Option<(u16, u16)>,
 }
 
 impl Args {
@@ -34,7 +131,84 @@ impl Args {
         self.unrestricted
     }
 
+    pub fn allow(&self) -> &[String] {
+        &self.allow
+    }
+
+    pub fn unlimited(&self) -> bool {
+        self.unlimited || self.unrestricted
+    }
+
+    pub fn prepare(&self) -> bool {
+        self.prepare
+    }
+
+    pub fn replay_cache(&self) -> bool {
+        self.replay_cache
+    }
+
     pub fn dry_run(&self) -> bool {
         self.dry_run
     }
+
+    pub fn list_tags(&self) -> bool {
+        self.list_tags
+    }
+
+    pub fn from(&self) -> Option<&str> {
+        self.from.as_deref()
+    }
+
+    pub fn to(&self) -> Option<&str> {
+        self.to.as_deref()
+    }
+
+    pub fn autoplay(&self) -> Option<Duration> {
+        self.autoplay.map(Duration::from_millis)
+    }
+
+    pub fn typer(&self) -> Option<usize> {
+        self.typer.map(|chars| chars as usize)
+    }
+
+    pub fn loop_wait(&self) -> Option<Duration> {
+        self.loop_wait.map(Duration::from_millis)
+    }
+
+    pub fn rerun_systems(&self) -> bool {
+        self.rerun_systems
+    }
+
+    pub fn prefetch(&self) -> Option<Duration> {
+        self.prefetch.map(Duration::from_millis)
+    }
+
+    pub fn record(&self) -> Option<&str> {
+        self.record.as_deref()
+    }
+
+    pub fn pause_delay(&self) -> Duration {
+        Duration::from_millis(self.pause_delay)
+    }
+
+    pub fn snapshot(&self) -> bool {
+        self.snapshot
+    }
+
+    pub fn snapshot_tags(&self) -> bool {
+        self.snapshot_tags
+    }
+
+    pub fn size(&self) -> Option<(u16, u16)> {
+        self.size
+    }
+}
+
+fn parse_size(size: &str) -> Result<(u16, u16), String> {
+    let err = || format!("expected size of the form COLSxROWS, got {size:?}");
+    let (cols, rows) = size.split_once('x').ok_or_else(err)?;
+    Ok((
+        cols.parse().map_err(|_| err())?,
+        rows.parse().map_err(|_| err())?,
+    ))
 }
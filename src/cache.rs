@@ -0,0 +1,137 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Output of the system commands of a lie, saved ahead of time so that it can be told without
+/// running them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    systems: Vec<CachedSystem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedSystem {
+    cmd_hash: String,
+    timestamp: u64,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    output: Vec<u8>,
+    requires_newline: bool,
+    #[serde(default)]
+    status: u8,
+}
+
+impl Cache {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let cache = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read cache '{}': {e}", path.display()))?;
+        Ok(serde_yaml::from_str(&cache)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        serde_yaml::to_writer(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    /// Record the output of the next system command.
+    pub fn push(&mut self, cmd: &str, output: &[u8], requires_newline: bool, status: u8) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs())
+            .unwrap_or_default();
+        self.systems.push(CachedSystem {
+            cmd_hash: hash(cmd),
+            timestamp,
+            output: output.to_vec(),
+            requires_newline,
+            status,
+        });
+    }
+
+    /// Get the output of the `id`th system command, provided it was made by `cmd`.
+    pub fn get(&self, id: usize, cmd: &str) -> Option<&CachedSystem> {
+        self.systems
+            .get(id)
+            .filter(|system| system.cmd_hash == hash(cmd))
+    }
+}
+
+impl CachedSystem {
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    pub fn requires_newline(&self) -> bool {
+        self.requires_newline
+    }
+
+    pub fn status(&self) -> u8 {
+        self.status
+    }
+}
+
+/// The cache file for the lie in `fname`, which sits next to it.
+pub fn path(fname: &Path) -> PathBuf {
+    let fname = fname.to_string_lossy();
+    let stem = fname.strip_suffix(".rhai").unwrap_or(&fname);
+    PathBuf::from(format!("{stem}.cache.yaml"))
+}
+
+fn hash(cmd: &str) -> String {
+    format!("{:x}", Sha256::digest(cmd))
+}
+
+/// Output is kept as base64, as commands need not write valid UTF-8.
+fn to_base64<S: Serializer>(output: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&BASE64.encode(output))
+}
+
+fn from_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    BASE64
+        .decode(String::deserialize(deserializer)?)
+        .map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cache_path() {
+        assert_eq!(path(Path::new("demo.rhai")), Path::new("demo.cache.yaml"));
+        assert_eq!(path(Path::new("demo")), Path::new("demo.cache.yaml"));
+        assert_eq!(
+            path(Path::new("talks/v1.2")),
+            Path::new("talks/v1.2.cache.yaml")
+        );
+    }
+
+    #[test]
+    fn save_and_load() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("lie.cache.yaml");
+
+        let mut cache = Cache::default();
+        cache.push("echo foo", b"foo\r\n", false, 0);
+        cache.push("printf bar", b"bar", true, 1);
+        cache.push("printf '\\377'", b"\xff\x1b[0m", true, 0);
+        cache.save(&path)?;
+
+        let cache = Cache::load(&path)?;
+        assert_eq!(cache.get(0, "echo foo").unwrap().output(), b"foo\r\n");
+        assert_eq!(
+            cache.get(2, "printf '\\377'").unwrap().output(),
+            b"\xff\x1b[0m"
+        );
+        assert!(cache.get(1, "printf bar").unwrap().requires_newline());
+        assert_eq!(cache.get(1, "printf bar").unwrap().status(), 1);
+        assert!(cache.get(1, "printf baz").is_none());
+        assert!(cache.get(2, "echo foo").is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use crate::input::Clock;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An asciicast v2 recording, built up by writing output to it as though it were a terminal.
+#[derive(Debug)]
+pub struct Cast {
+    width: u16,
+    height: u16,
+    title: Option<String>,
+    clock: Clock,
+    events: Vec<(Duration, Vec<u8>)>,
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+}
+
+impl Cast {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            title: None,
+            clock: Clock::default(),
+            events: vec![],
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        if self.title.is_none() {
+            self.title = Some(title.into());
+        }
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.clock.clone()
+    }
+
+    pub fn save<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs())
+            .unwrap_or_default();
+        let header = Header {
+            version: 2,
+            width: self.width,
+            height: self.height,
+            timestamp,
+            title: self.title.as_deref(),
+        };
+        serde_json::to_writer(&mut *w, &header)?;
+        writeln!(w)?;
+
+        for (time, data) in &self.events {
+            let event = (time.as_secs_f64(), "o", String::from_utf8_lossy(data));
+            serde_json::to_writer(&mut *w, &event)?;
+            writeln!(w)?;
+        }
+
+        w.flush()
+    }
+}
+
+impl Write for Cast {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.events.last_mut() {
+            Some((time, data)) if *time == self.clock.now() => data.extend_from_slice(buf),
+            _ => self.events.push((self.clock.now(), buf.to_vec())),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{input::Headless, lie, tale::Tale};
+    use std::error::Error;
+
+    #[test]
+    fn events() -> Result<(), Box<dyn Error>> {
+        let mut cast = Cast::new(80, 24);
+        cast.set_title("foo");
+        cast.set_title("bar");
+        write!(cast, "hello")?;
+        write!(cast, ", world\r\n")?;
+        cast.clock().advance(Duration::from_millis(1500));
+        write!(cast, "\"quoted\"")?;
+
+        let mut buf = vec![];
+        cast.save(&mut buf)?;
+        let saved = String::from_utf8(buf)?;
+        let lines: Vec<_> = saved.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(r#"{"version":2,"width":80,"height":24,"timestamp":"#));
+        assert!(lines[0].ends_with(r#","title":"foo"}"#));
+        assert_eq!(lines[1], r#"[0.0,"o","hello, world\r\n"]"#);
+        assert_eq!(lines[2], r#"[1.5,"o","\"quoted\""]"#);
+
+        Ok(())
+    }
+
+    #[test]
+    fn record() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            false,
+            r#"
+                lie.look(#{ title: "demo", speed: 0.0 });
+                lie.run("echo foo", "foo\n");
+            "#,
+        )?;
+
+        let mut tale = Tale::from(lie);
+        let mut cast = Cast::new(80, 24);
+        if let Some(title) = tale.title() {
+            cast.set_title(title);
+        }
+        let mut input = Headless::new(Duration::from_secs(1), cast.clock(), (80, 24));
+        tale.tell(&mut cast, &mut input)?;
+
+        assert_eq!(cast.title.as_deref(), Some("demo"));
+        let output: Vec<u8> = cast.events.iter().flat_map(|(_, d)| d.clone()).collect();
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("echo foo\r\n"), "{output:?}");
+        assert!(output.contains("foo\r\n"), "{output:?}");
+        assert_eq!(cast.clock.now(), Duration::from_secs(3));
+
+        Ok(())
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{fib::Fib, lie::Lie};
+use crate::{
+    fib::{Fib, Output},
+    lie::Lie,
+    markup,
+};
 
 pub struct DryRunBuilder {
     buf: Vec<String>,
@@ -48,22 +52,70 @@ impl DryRun for &[Fib] {
 impl DryRun for Fib {
     fn build_dry_run(&self, builder: &mut DryRunBuilder, depth: usize) {
         match self {
-            Self::Run { cmd, result } => {
-                builder.add_line(format!("$ {cmd}"), depth);
-                if !result.is_empty() {
-                    for line in result {
-                        builder.add_line(format!("# {line}"), depth);
+            Self::Run {
+                cmd,
+                result,
+                options,
+            } => {
+                if options.status == 0 {
+                    builder.add_line(format!("$ {cmd}"), depth);
+                } else {
+                    builder.add_line(format!("$ {cmd} (exits {})", options.status), depth);
+                }
+                for chunk in result {
+                    match chunk {
+                        Output::Stdout(line) => builder.add_line(format!("# {line}"), depth),
+                        Output::Stderr(line) => {
+                            builder.add_line(format!("# (stderr) {line}"), depth)
+                        }
                     }
                 }
             }
             Self::Show { text } => {
                 builder.add_line(format!("# {text}"), depth);
             }
-            Self::System { apparent_cmd, cmd } => {
-                if let Some(apparent_cmd) = apparent_cmd {
-                    builder.add_line(format!("! {apparent_cmd} (secretly calls: {cmd})"), depth);
+            Self::System {
+                apparent_cmd,
+                cmd,
+                options,
+            } => {
+                let mut line = if let Some(apparent_cmd) = apparent_cmd {
+                    format!("! {apparent_cmd} (secretly calls: {cmd})")
                 } else {
-                    builder.add_line(format!("! {cmd}"), depth);
+                    format!("! {cmd}")
+                };
+                if options.interactive {
+                    line.push_str(" (interactive)");
+                }
+                if let Some(timeout) = options.timeout {
+                    line.push_str(&format!(
+                        " (timeout: {})",
+                        pretty_duration::pretty_duration(&timeout, None)
+                    ));
+                }
+                if let Some(expected_status) = options.expected_status {
+                    line.push_str(&format!(" (expects status {expected_status})"));
+                }
+                if let Some(cwd) = &options.cwd {
+                    line.push_str(&format!(" (in {cwd})"));
+                }
+                if options.clear_env {
+                    line.push_str(" (clear env)");
+                }
+                if !options.env.is_empty() {
+                    line.push_str(&format!(
+                        " (env: {})",
+                        options
+                            .env
+                            .iter()
+                            .map(|(key, value)| format!("{key}={value}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+                builder.add_line(line, depth);
+                for line in options.fallback.iter().flatten() {
+                    builder.add_line(format!("# (fallback) {line}"), depth);
                 }
             }
             Self::Screen { apparent_cmd, fibs } => {
@@ -80,6 +132,10 @@ impl DryRun for Fib {
                 user,
                 host,
                 final_prompt,
+                typos,
+                prompt,
+                right_prompt,
+                stderr,
             } => {
                 let mut to_change = vec![];
                 if let Some(speed) = speed {
@@ -100,6 +156,18 @@ impl DryRun for Fib {
                 if let Some(final_prompt) = final_prompt {
                     to_change.push(("speed", final_prompt.to_string()));
                 }
+                if let Some(typos) = typos {
+                    to_change.push(("typos", typos.to_string()));
+                }
+                if let Some(prompt) = prompt {
+                    to_change.push(("prompt", format!("{:?}", prompt.to_string())));
+                }
+                if let Some(right_prompt) = right_prompt {
+                    to_change.push(("right_prompt", format!("{:?}", right_prompt.to_string())));
+                }
+                if let Some(stderr) = stderr {
+                    to_change.push(("stderr", format!("{:?}", markup::describe(stderr))));
+                }
 
                 builder.add_line(
                     format!(
@@ -113,6 +181,38 @@ impl DryRun for Fib {
                     depth,
                 );
             }
+            Self::Git {
+                branch,
+                dirty,
+                ahead,
+                behind,
+            } => {
+                let mut to_change = vec![];
+                if let Some(branch) = branch {
+                    to_change.push(("branch", branch.clone()));
+                }
+                if let Some(dirty) = dirty {
+                    to_change.push(("dirty", dirty.to_string()));
+                }
+                if let Some(ahead) = ahead {
+                    to_change.push(("ahead", ahead.to_string()));
+                }
+                if let Some(behind) = behind {
+                    to_change.push(("behind", behind.to_string()));
+                }
+
+                builder.add_line(
+                    format!(
+                        "(git: {})",
+                        to_change
+                            .iter()
+                            .map(|(field, value)| format!("{field}={value}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    depth,
+                );
+            }
             Self::Tag { name } => builder.add_line(format!("(tag) {name}"), depth),
             Self::Sleep { duration } => builder.add_line(
                 format!(
@@ -145,10 +245,14 @@ mod test {
                         lie.run("echo foo");
                         lie.run("echo asdf", "asdf");
                         lie.run("echo asdf", ["a", "s", "d", "f"]);
+                        lie.run("make", ["ok", #{ stderr: "oops\n" }], #{ status: 2 });
                         lie.show("foo");
+                        lie.show(style("ok").bold() + " " + markup("[green]done[/]"));
                         lie.cd("/root");
                         lie.system("ls");
                         lie.system("ls", "dir");
+                        lie.system("python3", #{ interactive: true });
+                        lie.system("curl wttr.in", #{ fallback: ["Sunny\n"] });
                         lie.sleep(100);
                         lie.pause();
                         lie.stop();
@@ -174,11 +278,20 @@ mod test {
                 # s
                 # d
                 # f
+                $ make (exits 2)
+                # ok
+                # (stderr) oops
+
                 # foo
+                # [bold]ok[/] [green]done[/]
                 $ cd /root
                 (look: cwd=/root)
                 ! ls
                 ! ls (secretly calls: dir)
+                ! python3 (interactive)
+                ! curl wttr.in
+                # (fallback) Sunny
+
                 (sleep) 100ms
                 (pause)
                 (STOP)
@@ -192,11 +305,20 @@ mod test {
                     # s
                     # d
                     # f
+                    $ make (exits 2)
+                    # ok
+                    # (stderr) oops
+
                     # foo
+                    # [bold]ok[/] [green]done[/]
                     $ cd /root
                     (look: cwd=/root)
                     ! ls
                     ! ls (secretly calls: dir)
+                    ! python3 (interactive)
+                    ! curl wttr.in
+                    # (fallback) Sunny
+
                     (sleep) 100ms
                     (pause)
                     (STOP)
@@ -211,11 +333,20 @@ mod test {
                     # s
                     # d
                     # f
+                    $ make (exits 2)
+                    # ok
+                    # (stderr) oops
+
                     # foo
+                    # [bold]ok[/] [green]done[/]
                     $ cd /root
                     (look: cwd=/root)
                     ! ls
                     ! ls (secretly calls: dir)
+                    ! python3 (interactive)
+                    ! curl wttr.in
+                    # (fallback) Sunny
+
                     (sleep) 100ms
                     (pause)
                     (STOP)
@@ -4,7 +4,6 @@ use std::{
     error::Error,
     path::PathBuf,
 };
-use subprocess::PopenError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -18,9 +17,24 @@ pub enum MendaxError {
     #[error("system calls are forbidden at this sandbox level")]
     SystemForbidden,
 
+    #[error("system call '{cmd}' is not allowed at this sandbox level")]
+    SystemNotAllowed { cmd: String },
+
     #[error("unknown colour {0:?}, expected one of: {}", .1.join(", "))]
     UnknownColour(String, &'static [&'static str]),
 
+    #[error("invalid markup {markup:?}: {reason}")]
+    InvalidMarkup {
+        markup: String,
+        reason: &'static str,
+    },
+
+    #[error("unknown placeholder {{{name}}}, expected one of: {}", .expected.join(", "))]
+    UnknownPlaceholder {
+        name: String,
+        expected: &'static [&'static str],
+    },
+
     #[error("^C")]
     KeyboardInterrupt,
 
@@ -54,8 +68,20 @@ pub enum MendaxError {
     #[error("tag '{name}' is reserved")]
     InvalidTagName { name: String },
 
-    #[error("subprocess error: {error}")]
-    Subprocess { error: PopenError },
+    #[error("unknown tag '{name}', expected one of: {}", .known.join(", "))]
+    UnknownTag { name: String, known: Vec<String> },
+
+    #[error("tag '{to}' does not come after tag '{from}'")]
+    TagOrder { from: String, to: String },
+
+    #[error("no cached output for '{cmd}', try preparing the cache again")]
+    Uncached { cmd: String },
+
+    #[error("cannot run '{cmd}': {error}")]
+    System {
+        cmd: String,
+        error: Box<dyn Error + Send + Sync>,
+    },
 }
 
 impl From<MendaxError> for EvalAltResult {
@@ -63,9 +89,3 @@ impl From<MendaxError> for EvalAltResult {
         EvalAltResult::ErrorSystem("mendax error".into(), Box::new(value))
     }
 }
-
-impl From<PopenError> for MendaxError {
-    fn from(error: PopenError) -> Self {
-        MendaxError::Subprocess { error }
-    }
-}
@@ -1,17 +1,22 @@
+use crate::markup::Text;
+use crate::prompt::Prompt;
+use crossterm::style::ContentStyle;
 use std::time::Duration;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Fib {
     Run {
         cmd: String,
-        result: Vec<String>,
+        result: Vec<Output>,
+        options: RunOptions,
     },
     Show {
-        text: String,
+        text: Text,
     },
     System {
         apparent_cmd: Option<String>,
         cmd: String,
+        options: SystemOptions,
     },
     Screen {
         apparent_cmd: Option<String>,
@@ -24,6 +29,16 @@ pub enum Fib {
         user: Option<String>,
         host: Option<String>,
         final_prompt: Option<bool>,
+        typos: Option<f64>,
+        prompt: Option<Prompt>,
+        right_prompt: Option<Prompt>,
+        stderr: Option<ContentStyle>,
+    },
+    Git {
+        branch: Option<String>,
+        dirty: Option<bool>,
+        ahead: Option<u32>,
+        behind: Option<u32>,
     },
     Tag {
         name: String,
@@ -34,7 +49,48 @@ pub enum Fib {
     Pause,
     Stop,
     Enter {
-        msg: String,
+        msg: Text,
     },
     Clear,
 }
+
+/// A chunk of output from a fake command, in the order it is printed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Output {
+    Stdout(Text),
+    Stderr(Text),
+}
+
+impl From<&str> for Output {
+    fn from(text: &str) -> Self {
+        Self::Stdout(text.into())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunOptions {
+    /// The chance of mistyping each character of the command, overriding the lie's look.
+    pub typos: Option<f64>,
+    /// The exit status the command pretends to have.
+    pub status: u8,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SystemOptions {
+    /// Forward the presenter's keystrokes to the command while it runs.
+    pub interactive: bool,
+    /// Stop the command if it runs for longer than this.
+    pub timeout: Option<Duration>,
+    /// The exit status the command should have, zero if unset.
+    pub expected_status: Option<u32>,
+    /// Output to show instead if the command fails or times out.
+    pub fallback: Option<Vec<String>>,
+    /// The directory to run the command in, relative to the lie file.
+    pub cwd: Option<String>,
+    /// Variables to add to the command's environment.
+    pub env: Vec<(String, String)>,
+    /// Start the command's environment empty, other than `TERM`.
+    pub clear_env: bool,
+    /// The chance of mistyping each character of the command, overriding the lie's look.
+    pub typos: Option<f64>,
+}
@@ -0,0 +1,309 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal,
+};
+use std::{cell::Cell, io, rc::Rc, thread, time::Duration};
+
+/// A source of presenter input.
+///
+/// Timing is also routed through the input so that sources which are not driven by a human can
+/// skip waits rather than sitting through them.
+pub trait Input {
+    fn begin(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn read(&mut self) -> io::Result<Event>;
+
+    /// Wait up to `timeout` for an event to become available to `read`.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    /// Whether input comes from someone who could interact with a running command.
+    fn is_interactive(&self) -> bool {
+        true
+    }
+
+    /// The column and row of the cursor, if known.
+    fn cursor_position(&mut self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// The number of columns and rows of the terminal, if known.
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// Whether Ctrl-C has been pressed, discarding any other input waiting to be read.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        while self.poll(Duration::ZERO)? {
+            if let Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers,
+                ..
+            }) = self.read()?
+            {
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Input from the controlling terminal.
+pub struct Terminal;
+
+impl Input for Terminal {
+    fn begin(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        terminal::disable_raw_mode()
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn cursor_position(&mut self) -> Option<(u16, u16)> {
+        cursor::position().ok()
+    }
+
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        terminal::size().ok()
+    }
+}
+
+/// Input which advances every pause after a fixed delay, without waiting in real time, on a
+/// terminal of the given size.
+pub struct Headless {
+    pause: Duration,
+    clock: Clock,
+    size: (u16, u16),
+}
+
+impl Headless {
+    pub fn new(pause: Duration, clock: Clock, size: (u16, u16)) -> Self {
+        Self { pause, clock, size }
+    }
+}
+
+impl Input for Headless {
+    fn read(&mut self) -> io::Result<Event> {
+        self.clock.advance(self.pause);
+        Ok(Event::Key(KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::NONE,
+        )))
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        self.clock.advance(timeout);
+        Ok(false)
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.clock.advance(duration);
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+
+    fn terminal_size(&self) -> Option<(u16, u16)> {
+        Some(self.size)
+    }
+}
+
+/// The bytes a terminal would send to a program when `key` is pressed.
+pub fn key_bytes(key: &KeyEvent) -> Option<Vec<u8>> {
+    let bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            vec![match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => c as u8 - b'a' + 1,
+                '@' | ' ' => 0,
+                '[' => 0x1b,
+                '\\' => 0x1c,
+                ']' => 0x1d,
+                '^' => 0x1e,
+                '_' => 0x1f,
+                _ => return None,
+            }]
+        }
+        KeyCode::Char(c) => {
+            let mut bytes = vec![];
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                bytes.push(0x1b);
+            }
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            bytes
+        }
+        KeyCode::Enter => b"\r".to_vec(),
+        KeyCode::Tab => b"\t".to_vec(),
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => b"\x7f".to_vec(),
+        KeyCode::Esc => b"\x1b".to_vec(),
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        KeyCode::Home => b"\x1b[H".to_vec(),
+        KeyCode::End => b"\x1b[F".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::F(n @ 1..=4) => vec![0x1b, b'O', b'P' + n - 1],
+        KeyCode::F(n @ 5..=12) => {
+            let code = [15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5];
+            format!("\x1b[{code}~").into_bytes()
+        }
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// A shared record of how much virtual time has passed.
+#[derive(Clone, Debug, Default)]
+pub struct Clock(Rc<Cell<Duration>>);
+
+impl Clock {
+    pub fn now(&self) -> Duration {
+        self.0.get()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Input which replays a fixed sequence of key events.
+    #[derive(Default)]
+    pub struct Scripted {
+        events: VecDeque<Event>,
+        screen: Option<((u16, u16), (u16, u16))>,
+    }
+
+    impl Scripted {
+        /// Press each of `keys` in turn, where '\n' is Enter and '\x1b' is Esc.
+        pub fn keys(keys: &str) -> Self {
+            Self::default().then_keys(keys)
+        }
+
+        pub fn then_keys(self, keys: &str) -> Self {
+            keys.chars().fold(self, |scripted, c| {
+                scripted.then(match c {
+                    '\n' => KeyCode::Enter,
+                    '\x1b' => KeyCode::Esc,
+                    c => KeyCode::Char(c),
+                })
+            })
+        }
+
+        /// Report the cursor to be at `cursor` on a terminal of the given `size`.
+        pub fn on_screen(mut self, cursor: (u16, u16), size: (u16, u16)) -> Self {
+            self.screen = Some((cursor, size));
+            self
+        }
+
+        pub fn then(mut self, code: KeyCode) -> Self {
+            self.events
+                .push_back(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+            self
+        }
+
+        pub fn then_ctrl(mut self, c: char) -> Self {
+            self.events.push_back(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::CONTROL,
+            )));
+            self
+        }
+    }
+
+    impl Input for Scripted {
+        fn read(&mut self) -> io::Result<Event> {
+            self.events
+                .pop_front()
+                .ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+        }
+
+        fn poll(&mut self, _: Duration) -> io::Result<bool> {
+            Ok(!self.events.is_empty())
+        }
+
+        fn sleep(&mut self, _: Duration) {}
+
+        fn cursor_position(&mut self) -> Option<(u16, u16)> {
+            self.screen.map(|(cursor, _)| cursor)
+        }
+
+        fn terminal_size(&self) -> Option<(u16, u16)> {
+            self.screen.map(|(_, size)| size)
+        }
+
+        // Keys meant for later pauses are left alone.
+        fn interrupted(&mut self) -> io::Result<bool> {
+            let ctrl_c = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+            if self.events.front() == Some(&ctrl_c) {
+                self.events.pop_front();
+                return Ok(true);
+            }
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn keys_to_bytes() {
+        let key = |code, modifiers| key_bytes(&KeyEvent::new(code, modifiers));
+        assert_eq!(
+            key(KeyCode::Char('a'), KeyModifiers::NONE),
+            Some(b"a".to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::Char('é'), KeyModifiers::NONE),
+            Some("é".into())
+        );
+        assert_eq!(
+            key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(vec![3])
+        );
+        assert_eq!(
+            key(KeyCode::Char('x'), KeyModifiers::ALT),
+            Some(b"\x1bx".to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::Up, KeyModifiers::NONE),
+            Some(b"\x1b[A".to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::F(2), KeyModifiers::NONE),
+            Some(b"\x1bOQ".to_vec())
+        );
+        assert_eq!(
+            key(KeyCode::F(5), KeyModifiers::NONE),
+            Some(b"\x1b[15~".to_vec())
+        );
+        assert_eq!(key(KeyCode::Null, KeyModifiers::NONE), None);
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{fib::Fib, MendaxError};
+use crate::{
+    fib::{Fib, Output, RunOptions, SystemOptions},
+    markup::{self, Text},
+    policy::SystemPolicy,
+    prompt::Prompt,
+    MendaxError,
+};
 use rhai::{
     Array, CustomType, Dynamic, Engine, EvalAltResult, FnPtr, Map, NativeCallContext, Scope,
     TypeBuilder,
@@ -7,18 +13,26 @@ use std::{
     cell::{Ref, RefCell, RefMut},
     collections::HashSet,
     fs,
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
     time::Duration,
 };
 
-pub fn read<P: AsRef<Path>>(fname: P, unrestricted: bool) -> Result<Lie, Box<EvalAltResult>> {
+/// Read the lie in `fname`. Engine limits are lifted if `unrestricted` and system commands may be
+/// declared if `policy` permits them.
+pub fn read<P: AsRef<Path>>(
+    fname: P,
+    unrestricted: bool,
+    policy: SystemPolicy,
+) -> Result<Lie, Box<EvalAltResult>> {
     let fname = fname.as_ref();
 
     let engine = engine(unrestricted);
 
     let mut scope = Scope::new();
-    scope.push("lie", SharedLie::new(unrestricted));
+    // System commands run relative to the lie, wherever it is told from.
+    let dir = fname.parent().unwrap_or(Path::new(""));
+    scope.push("lie", SharedLie::new(policy, dir));
 
     let src = get_src(fname)?;
     let ast = engine.compile_with_scope(&scope, src)?;
@@ -53,6 +67,7 @@ fn get_src(fname: &Path) -> Result<String, Box<EvalAltResult>> {
 fn engine(unrestricted: bool) -> Engine {
     let mut engine = Engine::new();
     engine.build_type::<SharedLie>();
+    register_styling(&mut engine);
 
     if !unrestricted {
         engine.set_max_array_size(1000);
@@ -69,6 +84,33 @@ fn engine(unrestricted: bool) -> Engine {
     engine
 }
 
+/// Register `markup` and `style`, which make styled `Text` to show in place of a string, and
+/// methods such as `bold` and `on_red` which style it further.
+fn register_styling(engine: &mut Engine) {
+    fn styled(text: Text, words: &str) -> Result<Text, Box<EvalAltResult>> {
+        let style = markup::style(words).map_err(|e| Box::<EvalAltResult>::new(e.into()))?;
+        Ok(text.styled(style))
+    }
+
+    engine
+        .register_type_with_name::<Text>("Text")
+        .register_fn("markup", |markup: &str| -> Result<_, Box<EvalAltResult>> {
+            Text::parse(markup).map_err(|e| Box::new(e.into()))
+        })
+        .register_fn("style", |text: &str| Text::from(text))
+        .register_fn("style", |text: &str, words: &str| {
+            styled(text.into(), words)
+        })
+        .register_fn("style", styled)
+        .register_fn("+", |a: Text, b: Text| a + b)
+        .register_fn("+", |a: Text, b: &str| a + b.into())
+        .register_fn("+", |a: &str, b: Text| Text::from(a) + b)
+        .register_fn("to_string", |text: &mut Text| text.to_string());
+    for (word, style) in markup::words() {
+        engine.register_fn(word, move |text: Text| text.styled(style));
+    }
+}
+
 #[derive(Clone, Debug)]
 struct SharedLie(Rc<RefCell<Lie>>);
 
@@ -96,8 +138,8 @@ impl TryFrom<SharedLie> for Lie {
 }
 
 impl SharedLie {
-    fn new(allow_system: bool) -> Self {
-        Self::from(Lie::new(allow_system))
+    fn new(policy: SystemPolicy, dir: &Path) -> Self {
+        Self::from(Lie::new(policy, dir))
     }
 
     fn lie(&self, ctx: &NativeCallContext) -> Result<Ref<'_, Lie>, Box<EvalAltResult>> {
@@ -131,8 +173,9 @@ impl SharedLie {
         ctx: NativeCallContext,
         lie: &mut Self,
         cmd: &str,
-        result: &str,
+        result: Dynamic,
     ) -> Result<(), Box<EvalAltResult>> {
+        let result = text_arg(&ctx, result)?;
         lie.lie_mut(&ctx)?.run_short(cmd, result);
         Ok(())
     }
@@ -143,10 +186,56 @@ impl SharedLie {
         cmd: &str,
         result: Array,
     ) -> Result<(), Box<EvalAltResult>> {
-        lie.lie_mut(&ctx)?.run_long(cmd, result)
+        lie.lie_mut(&ctx)?
+            .run_long(&ctx, cmd, result, RunOptions::default())
+    }
+
+    fn run_no_output_with_options(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        cmd: &str,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let options = Lie::run_options(&ctx, options)?;
+        lie.lie_mut(&ctx)?.run(cmd, vec![], options);
+        Ok(())
     }
 
-    fn show(ctx: NativeCallContext, lie: &mut Self, text: &str) -> Result<(), Box<EvalAltResult>> {
+    fn run_short_with_options(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        cmd: &str,
+        result: Dynamic,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let result = text_arg(&ctx, result)?;
+        let options = Lie::run_options(&ctx, options)?;
+        lie.lie_mut(&ctx)?
+            .run(cmd, vec![Output::Stdout(result)], options);
+        Ok(())
+    }
+
+    fn run_long_with_options(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        cmd: &str,
+        result: Array,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let options = Lie::run_options(&ctx, options)?;
+        lie.lie_mut(&ctx)?.run_long(&ctx, cmd, result, options)
+    }
+
+    fn status(ctx: NativeCallContext, lie: &mut Self) -> Result<i64, Box<EvalAltResult>> {
+        Ok(lie.lie(&ctx)?.status.into())
+    }
+
+    fn show(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        text: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let text = text_arg(&ctx, text)?;
         lie.lie_mut(&ctx)?.show(text);
         Ok(())
     }
@@ -170,7 +259,29 @@ impl SharedLie {
         apparent_cmd: &str,
         cmd: &str,
     ) -> Result<(), Box<EvalAltResult>> {
-        lie.lie_mut(&ctx)?.system(Some(apparent_cmd), cmd)
+        lie.lie_mut(&ctx)?
+            .system(Some(apparent_cmd), cmd, SystemOptions::default())
+    }
+
+    fn system_simple_with_options(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        cmd: &str,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let options = Lie::system_options(&ctx, options)?;
+        lie.lie_mut(&ctx)?.system(None, cmd, options)
+    }
+
+    fn system_with_options(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        apparent_cmd: &str,
+        cmd: &str,
+        options: Map,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let options = Lie::system_options(&ctx, options)?;
+        lie.lie_mut(&ctx)?.system(Some(apparent_cmd), cmd, options)
     }
 
     fn screen_simple(
@@ -198,6 +309,10 @@ impl SharedLie {
         lie.lie_mut(&ctx)?.look(ctx, options)
     }
 
+    fn git(ctx: NativeCallContext, lie: &mut Self, options: Map) -> Result<(), Box<EvalAltResult>> {
+        lie.lie_mut(&ctx)?.git(ctx, options)
+    }
+
     fn tag(ctx: NativeCallContext, lie: &mut Self, name: &str) -> Result<(), Box<EvalAltResult>> {
         lie.lie_mut(&ctx)?.tag(ctx, name)
     }
@@ -220,7 +335,12 @@ impl SharedLie {
         Ok(())
     }
 
-    fn enter(ctx: NativeCallContext, lie: &mut Self, msg: &str) -> Result<(), Box<EvalAltResult>> {
+    fn enter(
+        ctx: NativeCallContext,
+        lie: &mut Self,
+        msg: Dynamic,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let msg = text_arg(&ctx, msg)?;
         lie.lie_mut(&ctx)?.enter(msg);
         Ok(())
     }
@@ -235,16 +355,22 @@ impl SharedLie {
 pub struct Lie {
     fibs: Vec<Fib>,
     known_tags: Rc<RefCell<HashSet<String>>>,
-    allow_system: bool,
+    policy: SystemPolicy,
+    dir: PathBuf,
+    follow_checkouts: bool,
+    status: u8,
     root: bool,
 }
 
 impl Lie {
-    fn new(allow_system: bool) -> Self {
+    fn new(policy: SystemPolicy, dir: &Path) -> Self {
         Self {
             fibs: Vec::new(),
             known_tags: Rc::new(RefCell::new(HashSet::new())),
-            allow_system,
+            policy,
+            dir: dir.to_owned(),
+            follow_checkouts: false,
+            status: 0,
             root: true,
         }
     }
@@ -253,6 +379,11 @@ impl Lie {
         &self.fibs
     }
 
+    /// The directory of the file the lie was read from.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
     pub fn into_fibs(self) -> Vec<Fib> {
         self.fibs
     }
@@ -261,39 +392,127 @@ impl Lie {
         Self {
             fibs: vec![],
             known_tags: self.known_tags.clone(),
-            allow_system: self.allow_system,
+            policy: self.policy.clone(),
+            dir: self.dir.clone(),
+            follow_checkouts: self.follow_checkouts,
+            status: self.status,
             root: false,
         }
     }
 
     fn run_no_output(&mut self, cmd: &str) {
-        self.run(cmd, vec![])
+        self.run(cmd, vec![], RunOptions::default())
     }
 
-    fn run_short(&mut self, cmd: &str, result: &str) {
-        self.run(cmd, vec![result.into()])
+    fn run_short(&mut self, cmd: &str, result: Text) {
+        self.run(cmd, vec![Output::Stdout(result)], RunOptions::default())
     }
 
-    fn run_long(&mut self, cmd: &str, result: Array) -> Result<(), Box<EvalAltResult>> {
-        self.run(cmd, result.into_iter().map(|l| l.cast()).collect());
+    fn run_long(
+        &mut self,
+        ctx: &NativeCallContext,
+        cmd: &str,
+        result: Array,
+        options: RunOptions,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let result = result
+            .into_iter()
+            .map(|chunk| Self::output(ctx, chunk))
+            .collect::<Result<_, _>>()?;
+        self.run(cmd, result, options);
         Ok(())
     }
 
-    fn run(&mut self, cmd: &str, result: Vec<String>) {
-        let cmd = cmd.into();
-        self.fibs.push(Fib::Run { cmd, result });
+    /// Parse a chunk of a command's output, either text printed to stdout or a map such as
+    /// `#{ stderr: "..." }` naming the stream it is printed to.
+    fn output(ctx: &NativeCallContext, chunk: Dynamic) -> Result<Output, Box<EvalAltResult>> {
+        let type_name = chunk.type_name();
+        let mismatch = |expected: &str| {
+            Box::new(EvalAltResult::ErrorMismatchDataType(
+                expected.into(),
+                type_name.into(),
+                ctx.position(),
+            ))
+        };
+        if !chunk.is_map() {
+            return text(chunk)
+                .map(Output::Stdout)
+                .map_err(|_| mismatch("string, Text or map"));
+        }
+        let chunk: Map = chunk.cast();
+
+        let mut stdout = None;
+        let mut stderr = None;
+        apply_options(
+            ctx,
+            chunk,
+            &mut [
+                ("stdout", &mut |v: Dynamic| {
+                    stdout = Some(text(v)?);
+                    Ok(())
+                }),
+                ("stderr", &mut |v: Dynamic| {
+                    stderr = Some(text(v)?);
+                    Ok(())
+                }),
+            ],
+        )?;
+        match (stdout, stderr) {
+            (Some(text), None) => Ok(Output::Stdout(text)),
+            (None, Some(text)) => Ok(Output::Stderr(text)),
+            _ => Err(mismatch("map with one of stdout or stderr")),
+        }
     }
 
-    fn show(&mut self, text: &str) {
-        let text = text.into();
+    fn run(&mut self, cmd: &str, result: Vec<Output>, options: RunOptions) {
+        self.status = options.status;
+        self.fibs.push(Fib::Run {
+            cmd: cmd.into(),
+            result,
+            options,
+        });
+
+        if self.follow_checkouts {
+            if let Some(branch) = checked_out_branch(cmd) {
+                self.fibs.push(Fib::Git {
+                    branch: Some(branch.into()),
+                    dirty: None,
+                    ahead: Some(0),
+                    behind: Some(0),
+                });
+            }
+        }
+    }
+
+    fn run_options(
+        ctx: &NativeCallContext,
+        options: Map,
+    ) -> Result<RunOptions, Box<EvalAltResult>> {
+        let mut run_options = RunOptions::default();
+        apply_options(
+            ctx,
+            options,
+            &mut [
+                ("typos", &mut |v: Dynamic| {
+                    run_options.typos = Some(typo_rate(v)?);
+                    Ok(())
+                }),
+                ("status", &mut |v: Dynamic| {
+                    let status: i64 = v.try_cast().ok_or("i64")?;
+                    run_options.status = status.try_into().map_err(|_| "u8")?;
+                    Ok(())
+                }),
+            ],
+        )?;
+        Ok(run_options)
+    }
+
+    fn show(&mut self, text: Text) {
         self.fibs.push(Fib::Show { text });
     }
 
     fn cd(&mut self, dir: &str) {
-        self.fibs.push(Fib::Run {
-            cmd: format!("cd {dir}"),
-            result: vec![],
-        });
+        self.run(&format!("cd {dir}"), vec![], RunOptions::default());
         self.fibs.push(Fib::Look {
             cwd: Some(dir.into()),
             host: None,
@@ -301,25 +520,110 @@ impl Lie {
             speed: None,
             title: None,
             final_prompt: None,
+            typos: None,
+            prompt: None,
+            right_prompt: None,
+            stderr: None,
         });
     }
 
     fn system_simple(&mut self, cmd: &str) -> Result<(), Box<EvalAltResult>> {
-        self.system(None, cmd)
+        self.system(None, cmd, SystemOptions::default())
     }
 
-    fn system(&mut self, apparent_cmd: Option<&str>, cmd: &str) -> Result<(), Box<EvalAltResult>> {
-        if !self.allow_system {
-            return Err(Box::new(MendaxError::SystemForbidden.into()));
+    fn system(
+        &mut self,
+        apparent_cmd: Option<&str>,
+        cmd: &str,
+        options: SystemOptions,
+    ) -> Result<(), Box<EvalAltResult>> {
+        if !self.policy.permits(cmd, &options) {
+            return Err(Box::new(
+                match self.policy {
+                    SystemPolicy::Forbid => MendaxError::SystemForbidden,
+                    _ => MendaxError::SystemNotAllowed { cmd: cmd.into() },
+                }
+                .into(),
+            ));
         }
 
+        // The real status is only known once the lie is told.
+        self.status = 0;
         let apparent_cmd = apparent_cmd.map(ToOwned::to_owned);
         let cmd = cmd.into();
-        self.fibs.push(Fib::System { apparent_cmd, cmd });
+        self.fibs.push(Fib::System {
+            apparent_cmd,
+            cmd,
+            options,
+        });
 
         Ok(())
     }
 
+    fn system_options(
+        ctx: &NativeCallContext,
+        options: Map,
+    ) -> Result<SystemOptions, Box<EvalAltResult>> {
+        let mut system_options = SystemOptions::default();
+        apply_options(
+            ctx,
+            options,
+            &mut [
+                ("interactive", &mut |v: Dynamic| {
+                    system_options.interactive = v.try_cast().ok_or("bool")?;
+                    Ok(())
+                }),
+                ("timeout", &mut |v: Dynamic| {
+                    let millis: i64 = v.try_cast().ok_or("i64")?;
+                    system_options.timeout =
+                        Some(Duration::from_millis(millis.try_into().map_err(|_| "u64")?));
+                    Ok(())
+                }),
+                ("expected_status", &mut |v: Dynamic| {
+                    let status: i64 = v.try_cast().ok_or("i64")?;
+                    system_options.expected_status = Some(status.try_into().map_err(|_| "u32")?);
+                    Ok(())
+                }),
+                ("fallback", &mut |v: Dynamic| {
+                    system_options.fallback = Some(if v.is_string() {
+                        vec![v.cast()]
+                    } else {
+                        v.try_cast::<Array>()
+                            .ok_or("string or array")?
+                            .into_iter()
+                            .map(|line| line.try_cast())
+                            .collect::<Option<_>>()
+                            .ok_or("array of strings")?
+                    });
+                    Ok(())
+                }),
+                ("cwd", &mut |v: Dynamic| {
+                    system_options.cwd = Some(v.try_cast().ok_or("string")?);
+                    Ok(())
+                }),
+                ("env", &mut |v: Dynamic| {
+                    system_options.env = v
+                        .try_cast::<Map>()
+                        .ok_or("map")?
+                        .into_iter()
+                        .map(|(k, v)| Some((k.into(), v.try_cast()?)))
+                        .collect::<Option<_>>()
+                        .ok_or("map of strings")?;
+                    Ok(())
+                }),
+                ("clear_env", &mut |v: Dynamic| {
+                    system_options.clear_env = v.try_cast().ok_or("bool")?;
+                    Ok(())
+                }),
+                ("typos", &mut |v: Dynamic| {
+                    system_options.typos = Some(typo_rate(v)?);
+                    Ok(())
+                }),
+            ],
+        )?;
+        Ok(system_options)
+    }
+
     fn screen_simple(
         &mut self,
         ctx: NativeCallContext,
@@ -341,9 +645,12 @@ impl Lie {
         let child = SharedLie::from(self.child());
         f.call_within_context(&ctx, (child.clone(),))?;
 
+        let child = child.lie(&ctx)?;
+        self.status = child.status;
+        self.follow_checkouts = child.follow_checkouts;
         self.fibs.push(Fib::Screen {
             apparent_cmd: apparent_cmd.map(ToOwned::to_owned),
-            fibs: child.lie(&ctx)?.fibs.clone(),
+            fibs: child.fibs.clone(),
         });
 
         Ok(())
@@ -356,13 +663,15 @@ impl Lie {
         let mut host = None;
         let mut user = None;
         let mut final_prompt = None;
-
-        {
-            #[allow(clippy::type_complexity)]
-            let mut action_list: [(
-                &str,
-                &mut dyn FnMut(Dynamic) -> Result<(), &'static str>,
-            ); 6] = [
+        let mut typos = None;
+        let mut prompt = None;
+        let mut right_prompt = None;
+        let mut stderr = None;
+
+        apply_options(
+            &ctx,
+            options,
+            &mut [
                 ("speed", &mut |v: Dynamic| {
                     speed = Some(v.try_cast().ok_or("f64")?);
                     Ok(())
@@ -387,43 +696,35 @@ impl Lie {
                     final_prompt = Some(v.try_cast().ok_or("string")?);
                     Ok(())
                 }),
-            ];
-
-            for (k, v) in options.iter() {
-                let mut found = false;
-                let k = k.as_str();
-                'actions: for (name, action) in &mut action_list {
-                    if k != *name {
-                        continue;
-                    }
-                    let type_name = v.type_name();
-                    action(v.clone()).map_err(|e| {
-                        EvalAltResult::ErrorMismatchDataType(
-                            e.into(),
-                            type_name.into(),
-                            ctx.position(),
-                        )
-                    })?;
-                    found = true;
-                    break 'actions;
-                }
-
-                if !found {
-                    return Err(Box::new(
-                        MendaxError::UnknownField {
-                            field: k.to_owned(),
-                            expected: {
-                                let mut expected: Vec<_> =
-                                    action_list.iter().map(|(k, _)| k.to_owned()).collect();
-                                expected.sort();
-                                expected
-                            },
-                        }
-                        .into(),
-                    ));
-                }
-            }
-        }
+                ("typos", &mut |v: Dynamic| {
+                    typos = Some(typo_rate(v)?);
+                    Ok(())
+                }),
+                ("prompt", &mut |v: Dynamic| {
+                    prompt = Some(v.try_cast::<String>().ok_or("string")?);
+                    Ok(())
+                }),
+                ("right_prompt", &mut |v: Dynamic| {
+                    right_prompt = Some(v.try_cast::<String>().ok_or("string")?);
+                    Ok(())
+                }),
+                ("stderr", &mut |v: Dynamic| {
+                    stderr = Some(v.try_cast::<String>().ok_or("string")?);
+                    Ok(())
+                }),
+            ],
+        )?;
+        let [prompt, right_prompt] = [prompt, right_prompt].map(|prompt| {
+            prompt
+                .map(|prompt| Prompt::new(&prompt))
+                .transpose()
+                .map_err(|e| Box::<EvalAltResult>::new(e.into()))
+        });
+        let (prompt, right_prompt) = (prompt?, right_prompt?);
+        let stderr = stderr
+            .map(|words| markup::style(&words))
+            .transpose()
+            .map_err(|e| Box::<EvalAltResult>::new(e.into()))?;
 
         self.fibs.push(Fib::Look {
             speed,
@@ -432,6 +733,55 @@ impl Lie {
             host,
             user,
             final_prompt,
+            typos,
+            prompt,
+            right_prompt,
+            stderr,
+        });
+
+        Ok(())
+    }
+
+    fn git(&mut self, ctx: NativeCallContext, options: Map) -> Result<(), Box<EvalAltResult>> {
+        let mut branch = None;
+        let mut dirty = None;
+        let mut ahead = None;
+        let mut behind = None;
+
+        apply_options(
+            &ctx,
+            options,
+            &mut [
+                ("branch", &mut |v: Dynamic| {
+                    branch = Some(v.try_cast().ok_or("string")?);
+                    Ok(())
+                }),
+                ("dirty", &mut |v: Dynamic| {
+                    dirty = Some(v.try_cast().ok_or("bool")?);
+                    Ok(())
+                }),
+                ("ahead", &mut |v: Dynamic| {
+                    let commits: i64 = v.try_cast().ok_or("i64")?;
+                    ahead = Some(commits.try_into().map_err(|_| "u32")?);
+                    Ok(())
+                }),
+                ("behind", &mut |v: Dynamic| {
+                    let commits: i64 = v.try_cast().ok_or("i64")?;
+                    behind = Some(commits.try_into().map_err(|_| "u32")?);
+                    Ok(())
+                }),
+                ("follow_checkouts", &mut |v: Dynamic| {
+                    self.follow_checkouts = v.try_cast().ok_or("bool")?;
+                    Ok(())
+                }),
+            ],
+        )?;
+
+        self.fibs.push(Fib::Git {
+            branch,
+            dirty,
+            ahead,
+            behind,
         });
 
         Ok(())
@@ -481,8 +831,8 @@ impl Lie {
         self.fibs.push(Fib::Stop);
     }
 
-    fn enter(&mut self, msg: &str) {
-        self.fibs.push(Fib::Enter { msg: msg.into() });
+    fn enter(&mut self, msg: Text) {
+        self.fibs.push(Fib::Enter { msg });
     }
 
     fn clear(&mut self) {
@@ -490,6 +840,127 @@ impl Lie {
     }
 }
 
+/// A named option and how to apply its value, failing with the name of the expected type.
+type OptionAction<'a> = (
+    &'static str,
+    &'a mut dyn FnMut(Dynamic) -> Result<(), &'static str>,
+);
+
+/// The branch which `cmd` switches to, if it is a `git checkout` or `git switch`. As the files
+/// in the pretend repository are unknown, a lone argument to `git checkout` is taken to be a
+/// file rather than a branch only if it looks like one, as in `.` or `README.md`.
+fn checked_out_branch(cmd: &str) -> Option<&str> {
+    let mut words = cmd.split_whitespace();
+    if words.next() != Some("git") {
+        return None;
+    }
+    let switch = match words.next() {
+        Some("switch") => true,
+        Some("checkout") => false,
+        _ => return None,
+    };
+
+    let mut track = false;
+    let mut args = vec![];
+    while let Some(word) = words.next() {
+        match word {
+            "-b" | "-B" | "-c" | "-C" | "--create" | "--force-create" | "--orphan" => {
+                return words.next();
+            }
+            "-t" | "--track" => track = true,
+            // Only files are checked out, or no branch is.
+            "--" | "-d" | "--detach" => return None,
+            _ if word.starts_with("--track=") => track = true,
+            _ if word.starts_with('-') => {}
+            _ => args.push(word),
+        }
+    }
+
+    match args[..] {
+        [branch] if switch || !looks_like_path(branch) => Some(match branch.split_once('/') {
+            Some((_remote, branch)) if track => branch,
+            _ => branch,
+        }),
+        // Anything after the first argument is a file to check out from it.
+        _ => None,
+    }
+}
+
+/// Whether `arg` looks like a path rather than a branch name.
+fn looks_like_path(arg: &str) -> bool {
+    arg == "."
+        || arg == ".."
+        || arg.starts_with("./")
+        || arg.starts_with("../")
+        || arg.contains('*')
+        || arg.rsplit('/').next().is_some_and(|name| {
+            name.rsplit_once('.').is_some_and(|(stem, extension)| {
+                !stem.is_empty()
+                    && !extension.is_empty()
+                    && extension.chars().all(|c| c.is_ascii_alphabetic())
+            })
+        })
+}
+
+/// The chance of mistyping each character, which must be between 0 and 1.
+fn typo_rate(v: Dynamic) -> Result<f64, &'static str> {
+    let rate: f64 = v.try_cast().ok_or("f64")?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err("f64 between 0 and 1");
+    }
+    Ok(rate)
+}
+
+/// Text to show, either a plain string or styled `Text`.
+fn text(v: Dynamic) -> Result<Text, &'static str> {
+    if v.is_string() {
+        return Ok(v.cast::<String>().into());
+    }
+    v.try_cast().ok_or("string or Text")
+}
+
+fn text_arg(ctx: &NativeCallContext, v: Dynamic) -> Result<Text, Box<EvalAltResult>> {
+    let type_name = v.type_name();
+    text(v).map_err(|expected| {
+        Box::new(EvalAltResult::ErrorMismatchDataType(
+            expected.into(),
+            type_name.into(),
+            ctx.position(),
+        ))
+    })
+}
+
+fn apply_options(
+    ctx: &NativeCallContext,
+    options: Map,
+    action_list: &mut [OptionAction],
+) -> Result<(), Box<EvalAltResult>> {
+    for (k, v) in options.iter() {
+        let k = k.as_str();
+        let Some((_, action)) = action_list.iter_mut().find(|(name, _)| k == *name) else {
+            return Err(Box::new(
+                MendaxError::UnknownField {
+                    field: k.to_owned(),
+                    expected: {
+                        let mut expected: Vec<_> =
+                            action_list.iter().map(|(k, _)| k.to_owned()).collect();
+                        expected.sort();
+                        expected
+                    },
+                }
+                .into(),
+            ));
+        };
+
+        let type_name = v.type_name();
+        action(v.clone()).map_err(|e| {
+            EvalAltResult::ErrorMismatchDataType(e.into(), type_name.into(), ctx.position())
+        })?;
+    }
+
+    Ok(())
+}
+
 impl CustomType for SharedLie {
     fn build(mut builder: TypeBuilder<Self>) {
         builder
@@ -497,13 +968,20 @@ impl CustomType for SharedLie {
             .with_fn("run", Self::run_no_output)
             .with_fn("run", Self::run_short)
             .with_fn("run", Self::run_long)
+            .with_fn("run", Self::run_no_output_with_options)
+            .with_fn("run", Self::run_short_with_options)
+            .with_fn("run", Self::run_long_with_options)
+            .with_get("status", Self::status)
             .with_fn("show", Self::show)
             .with_fn("cd", Self::cd)
             .with_fn("system", Self::system_simple)
             .with_fn("system", Self::system)
+            .with_fn("system", Self::system_simple_with_options)
+            .with_fn("system", Self::system_with_options)
             .with_fn("screen", Self::screen_simple)
             .with_fn("screen", Self::screen)
             .with_fn("look", Self::look)
+            .with_fn("git", Self::git)
             .with_fn("tag", Self::tag)
             .with_fn("sleep", Self::sleep)
             .with_fn("pause", Self::pause)
@@ -516,6 +994,7 @@ impl CustomType for SharedLie {
 #[cfg(test)]
 pub(crate) mod test {
     use super::*;
+    use crossterm::style::{ContentStyle, Stylize};
     use regex::Regex;
     use std::{
         error::Error,
@@ -530,7 +1009,12 @@ pub(crate) mod test {
 
         fs::write(lie_path, script)?;
 
-        read(lie_path.as_os_str().to_str().unwrap(), unrestricted).map_err(|e| e.into())
+        read(
+            lie_path.as_os_str().to_str().unwrap(),
+            unrestricted,
+            SystemPolicy::new(unrestricted, &[]),
+        )
+        .map_err(|e| e.into())
     }
 
     #[test]
@@ -540,8 +1024,8 @@ pub(crate) mod test {
         let mut file = File::create(&path)?;
         file.write_all(r#"lie.show("hello");"#.as_bytes())?;
 
-        assert!(read(&path, false).is_ok());
-        assert!(read(path.with_extension(""), false).is_ok());
+        assert!(read(&path, false, SystemPolicy::Forbid).is_ok());
+        assert!(read(path.with_extension(""), false, SystemPolicy::Forbid).is_ok());
 
         Ok(())
     }
@@ -554,6 +1038,10 @@ pub(crate) mod test {
                 lie.run("foo");
                 lie.run("bar", "qwer");
                 lie.run("baz", ["asdf", "fdsa"]);
+                lie.run("foo", #{ typos: 0.5 });
+                lie.run("bar", "qwer", #{ typos: 0.0, status: 2 });
+                lie.run("baz", ["asdf"], #{});
+                lie.run("make", ["ok\n", #{ stderr: "warning\n" }, #{ stdout: "done\n" }]);
             "#,
         )?;
 
@@ -562,19 +1050,151 @@ pub(crate) mod test {
             &[
                 Fib::Run {
                     cmd: "foo".into(),
-                    result: vec![]
+                    result: vec![],
+                    options: RunOptions::default(),
+                },
+                Fib::Run {
+                    cmd: "bar".into(),
+                    result: vec!["qwer".into()],
+                    options: RunOptions::default(),
+                },
+                Fib::Run {
+                    cmd: "baz".into(),
+                    result: vec!["asdf".into(), "fdsa".into()],
+                    options: RunOptions::default(),
+                },
+                Fib::Run {
+                    cmd: "foo".into(),
+                    result: vec![],
+                    options: RunOptions {
+                        typos: Some(0.5),
+                        ..Default::default()
+                    },
                 },
                 Fib::Run {
                     cmd: "bar".into(),
-                    result: vec!["qwer".into()]
+                    result: vec!["qwer".into()],
+                    options: RunOptions {
+                        typos: Some(0.0),
+                        status: 2,
+                    },
                 },
                 Fib::Run {
                     cmd: "baz".into(),
-                    result: vec!["asdf".into(), "fdsa".into()]
+                    result: vec!["asdf".into()],
+                    options: RunOptions::default(),
+                },
+                Fib::Run {
+                    cmd: "make".into(),
+                    result: vec![
+                        "ok\n".into(),
+                        Output::Stderr("warning\n".into()),
+                        Output::Stdout("done\n".into()),
+                    ],
+                    options: RunOptions::default(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            test_script(false, r#"lie.run("foo", #{ typos: 2.0 });"#)
+                .unwrap_err()
+                .to_string(),
+            "Data type incorrect: f64 (expecting f64 between 0 and 1) (line 1, position 5)"
+        );
+        assert_eq!(
+            test_script(false, r#"lie.run("foo", [#{ stdout: "a", stderr: "b" }]);"#)
+                .unwrap_err()
+                .to_string(),
+            "Data type incorrect: map (expecting map with one of stdout or stderr) (line 1, position 5)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn styling() -> Result<(), Box<dyn Error>> {
+        let lie = test_script(
+            false,
+            r#"
+                lie.show(markup("[bold]a[[b] [on_#ff0000]c[/][/]"));
+                lie.show("[bold]" + style("ok").green().bold() + style(" fine", "dim"));
+                lie.enter(style("ok", "red").style("underline"));
+                lie.run("make", style("done").italic(), #{ status: 1 });
+                lie.run("make", [style("done").blue(), #{ stderr: markup("[red]oops[/]") }]);
+            "#,
+        )?;
+
+        let text = |markup| Text::parse(markup).unwrap();
+        assert_eq!(
+            lie.fibs(),
+            &[
+                Fib::Show {
+                    text: text("[bold]a[[b] [on_#ff0000]c[/][/]"),
+                },
+                Fib::Show {
+                    text: Text::from("[bold]") + text("[bold][green]ok[/][/][dim] fine[/]"),
+                },
+                Fib::Enter {
+                    msg: text("[underline][red]ok[/][/]"),
+                },
+                Fib::Run {
+                    cmd: "make".into(),
+                    result: vec![Output::Stdout(text("[italic]done[/]"))],
+                    options: RunOptions {
+                        status: 1,
+                        ..Default::default()
+                    },
+                },
+                Fib::Run {
+                    cmd: "make".into(),
+                    result: vec![
+                        Output::Stdout(text("[blue]done[/]")),
+                        Output::Stderr(text("[red]oops[/]")),
+                    ],
+                    options: RunOptions::default(),
                 },
             ]
         );
 
+        assert!(test_script(false, r#"lie.show(markup("[pink]a[/]"));"#)
+            .unwrap_err()
+            .to_string()
+            .starts_with("mendax error: unknown colour \"pink\", expected one of: black, "));
+        assert_eq!(
+            test_script(false, r#"lie.show(1);"#)
+                .unwrap_err()
+                .to_string(),
+            "Data type incorrect: i64 (expecting string or Text) (line 1, position 5)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn status() -> Result<(), Box<dyn Error>> {
+        let lie = test_script(
+            true,
+            r#"
+                lie.run("make", #{ status: 2 });
+                lie.show(lie.status.to_string());
+                lie.system("ls");
+                lie.show(lie.status.to_string());
+                lie.screen(|lie| {
+                    lie.run("make", #{ status: 3 });
+                    lie.git(#{ follow_checkouts: true });
+                });
+                lie.show(lie.status.to_string());
+                lie.run("git checkout main");
+            "#,
+        )?;
+
+        let fibs = lie.fibs();
+        assert_eq!(fibs[1], Fib::Show { text: "2".into() });
+        assert_eq!(fibs[3], Fib::Show { text: "0".into() });
+        assert_eq!(fibs[5], Fib::Show { text: "3".into() });
+        assert!(matches!(&fibs[7], Fib::Git { branch: Some(branch), .. } if branch == "main"));
+
         Ok(())
     }
 
@@ -612,6 +1232,7 @@ pub(crate) mod test {
                 Fib::Run {
                     cmd: "cd /foo/bar".into(),
                     result: vec![],
+                    options: RunOptions::default(),
                 },
                 Fib::Look {
                     cwd: Some("/foo/bar".into()),
@@ -620,6 +1241,10 @@ pub(crate) mod test {
                     user: None,
                     host: None,
                     final_prompt: None,
+                    typos: None,
+                    prompt: None,
+                    right_prompt: None,
+                    stderr: None,
                 }
             ]
         );
@@ -644,13 +1269,114 @@ pub(crate) mod test {
                     Fib::System {
                         cmd: "ls".into(),
                         apparent_cmd: None,
+                        options: SystemOptions::default(),
                     },
                     Fib::System {
                         cmd: "ls -Al".into(),
                         apparent_cmd: Some("la".into()),
+                        options: SystemOptions::default(),
+                    },
+                ]
+            );
+        }
+
+        {
+            let lie = test_script(
+                true,
+                r#"
+                    lie.system("python3", #{ interactive: true });
+                    lie.system("vim", "vim -u NONE", #{ interactive: true });
+                "#,
+            )?;
+
+            assert_eq!(
+                lie.fibs(),
+                &[
+                    Fib::System {
+                        cmd: "python3".into(),
+                        apparent_cmd: None,
+                        options: SystemOptions {
+                            interactive: true,
+                            ..Default::default()
+                        },
+                    },
+                    Fib::System {
+                        cmd: "vim -u NONE".into(),
+                        apparent_cmd: Some("vim".into()),
+                        options: SystemOptions {
+                            interactive: true,
+                            ..Default::default()
+                        },
+                    },
+                ]
+            );
+
+            assert_eq!(
+                test_script(
+                    true,
+                    r#"
+                        lie.system("curl example.com", #{
+                            timeout: 500,
+                            expected_status: 0,
+                            fallback: ["<html>\n", "</html>\n"],
+                        });
+                        lie.system("ping -c1 example.com", #{ fallback: "64 bytes\n" });
+                    "#,
+                )?
+                .fibs(),
+                &[
+                    Fib::System {
+                        cmd: "curl example.com".into(),
+                        apparent_cmd: None,
+                        options: SystemOptions {
+                            timeout: Some(Duration::from_millis(500)),
+                            expected_status: Some(0),
+                            fallback: Some(vec!["<html>\n".into(), "</html>\n".into()]),
+                            ..Default::default()
+                        },
+                    },
+                    Fib::System {
+                        cmd: "ping -c1 example.com".into(),
+                        apparent_cmd: None,
+                        options: SystemOptions {
+                            fallback: Some(vec!["64 bytes\n".into()]),
+                            ..Default::default()
+                        },
                     },
                 ]
             );
+
+            assert_eq!(
+                test_script(
+                    true,
+                    r#"lie.system("make", #{ cwd: "demo", env: #{ CC: "clang" }, clear_env: true, typos: 0.1 });"#,
+                )?
+                .fibs(),
+                &[Fib::System {
+                    cmd: "make".into(),
+                    apparent_cmd: None,
+                    options: SystemOptions {
+                        cwd: Some("demo".into()),
+                        env: vec![("CC".into(), "clang".into())],
+                        clear_env: true,
+                        typos: Some(0.1),
+                        ..Default::default()
+                    },
+                }]
+            );
+
+            assert_eq!(
+                test_script(true, r#"lie.system("ls", #{ interactive: 1 });"#)
+                    .unwrap_err()
+                    .to_string(),
+                "Data type incorrect: i64 (expecting bool) (line 1, position 5)",
+            );
+            assert_eq!(
+                test_script(true, r#"lie.system("ls", #{ foo: true });"#)
+                    .unwrap_err()
+                    .to_string(),
+                "mendax error: unknown field \"foo\", expected one of: clear_env, cwd, env, expected_status, fallback, interactive, timeout, typos",
+            );
         }
 
         {
@@ -659,10 +1385,32 @@ pub(crate) mod test {
                     e.to_string(),
                     "mendax error: system calls are forbidden at this sandbox level"
                 ),
-                _ => assert!(false, "system was allowed"),
+                _ => panic!("system was allowed"),
             }
         }
 
+        {
+            let dir = tempfile::tempdir()?;
+            let lie_path = dir.path().join("test-lie.rhai");
+            fs::write(
+                &lie_path,
+                r#"
+                    lie.system("kubectl get pods");
+                    lie.system("kubectl delete pods");
+                "#,
+            )?;
+            assert_eq!(
+                read(
+                    &lie_path,
+                    false,
+                    SystemPolicy::new(false, &["kubectl get".into()])
+                )
+                .unwrap_err()
+                .to_string(),
+                "mendax error: system call 'kubectl delete pods' is not allowed at this sandbox level"
+            );
+        }
+
         Ok(())
     }
 
@@ -684,6 +1432,7 @@ pub(crate) mod test {
                     fibs: vec![Fib::System {
                         cmd: "sudo ls /root".into(),
                         apparent_cmd: Some("ls".into()),
+                        options: SystemOptions::default(),
                     }]
                 }]
             );
@@ -719,6 +1468,7 @@ pub(crate) mod test {
                     fibs: vec![Fib::System {
                         cmd: "sudo ls /root".into(),
                         apparent_cmd: Some("ls".into()),
+                        options: SystemOptions::default(),
                     }]
                 }]
             );
@@ -829,6 +1579,10 @@ pub(crate) mod test {
                 host: None,
                 user: None,
                 final_prompt: None,
+                typos: None,
+                prompt: None,
+                right_prompt: None,
+                stderr: None,
             }],
         );
 
@@ -843,6 +1597,10 @@ pub(crate) mod test {
                         user: "methos",
                         host: "gaia",
                         final_prompt: false,
+                        typos: 0.02,
+                        prompt: "[bold]{user}[/] {cwd} % ",
+                        right_prompt: "{time}",
+                        stderr: "bold red",
                     });
                 "#
             )?
@@ -854,6 +1612,10 @@ pub(crate) mod test {
                 host: Some("gaia".into()),
                 user: Some("methos".into()),
                 final_prompt: Some(false),
+                typos: Some(0.02),
+                prompt: Some(Prompt::new("[bold]{user}[/] {cwd} % ")?),
+                right_prompt: Some(Prompt::new("{time}")?),
+                stderr: Some(ContentStyle::new().red().bold()),
             }]
         );
 
@@ -867,6 +1629,77 @@ pub(crate) mod test {
         Ok(())
     }
 
+    #[test]
+    fn git() -> Result<(), Box<dyn Error>> {
+        let lie = test_script(
+            false,
+            r#"
+                lie.run("git checkout foo");
+                lie.git(#{ branch: "main", dirty: true, ahead: 2, follow_checkouts: true });
+                lie.run("git checkout -b feature", "Switched to a new branch 'feature'");
+                lie.run("git checkout -- README.md");
+                lie.run("git checkout README.md");
+                lie.run("git checkout main src/lie.rs");
+                lie.run("git checkout .");
+                lie.run("git switch -");
+                lie.run("git checkout --detach release-1.2");
+                lie.run("git checkout -t origin/fix");
+                lie.run("git switch --track=direct origin/release-1.2");
+                lie.run("git checkout -b topic --track origin/main");
+            "#,
+        )?;
+
+        let run = |cmd: &str, result: &[&str]| Fib::Run {
+            cmd: cmd.into(),
+            result: result.iter().map(|line| Output::from(*line)).collect(),
+            options: RunOptions::default(),
+        };
+        let checked_out = |branch: &str| Fib::Git {
+            branch: Some(branch.into()),
+            dirty: None,
+            ahead: Some(0),
+            behind: Some(0),
+        };
+        assert_eq!(
+            lie.fibs(),
+            &[
+                run("git checkout foo", &[]),
+                Fib::Git {
+                    branch: Some("main".into()),
+                    dirty: Some(true),
+                    ahead: Some(2),
+                    behind: None,
+                },
+                run(
+                    "git checkout -b feature",
+                    &["Switched to a new branch 'feature'"]
+                ),
+                checked_out("feature"),
+                run("git checkout -- README.md", &[]),
+                run("git checkout README.md", &[]),
+                run("git checkout main src/lie.rs", &[]),
+                run("git checkout .", &[]),
+                run("git switch -", &[]),
+                run("git checkout --detach release-1.2", &[]),
+                run("git checkout -t origin/fix", &[]),
+                checked_out("fix"),
+                run("git switch --track=direct origin/release-1.2", &[]),
+                checked_out("release-1.2"),
+                run("git checkout -b topic --track origin/main", &[]),
+                checked_out("topic"),
+            ]
+        );
+
+        assert_eq!(
+            test_script(false, r#"lie.git(#{ ahead: -1 });"#)
+                .unwrap_err()
+                .to_string(),
+            "Data type incorrect: i64 (expecting u32) (line 1, position 5)"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn sleep() -> Result<(), Box<dyn Error>> {
         assert_eq!(
@@ -918,9 +1751,13 @@ pub(crate) mod test {
         fs::write(lie_path_exact, r#"lie.show("asdf")"#)?;
         fs::write(lie_path_inferred, r#"lie.show("asdf")"#)?;
 
-        let err_string = read(lie_path_exact.as_os_str().to_str().unwrap(), false)
-            .unwrap_err()
-            .to_string();
+        let err_string = read(
+            lie_path_exact.as_os_str().to_str().unwrap(),
+            false,
+            SystemPolicy::Forbid,
+        )
+        .unwrap_err()
+        .to_string();
 
         let re = Regex::new("^mendax error: ambiguous source: both .* and .* exist$").unwrap();
         assert!(re.is_match(&err_string), "unexpected error: {err_string}");
@@ -1,10 +1,20 @@
 mod args;
+mod cache;
+mod cast;
 mod dry_run;
 mod error;
 mod fib;
 mod init;
+mod input;
 mod lie;
+mod markup;
+mod outline;
+mod picker;
+mod policy;
+mod prompt;
+mod snapshot;
 mod tale;
+mod typos;
 
 pub use error::MendaxError;
 
@@ -13,12 +23,21 @@ pub use error::MendaxError;
 extern crate pretty_assertions;
 
 use crate::args::Args;
+use crate::cache::Cache;
+use crate::cast::Cast;
+use crate::input::{Headless, Terminal};
 use crate::tale::Tale;
 use clap::Parser;
+use crossterm::terminal;
 use dry_run::DryRun;
-use std::io::stdout;
+use outline::Outline;
+use policy::SystemPolicy;
+use std::error::Error;
+use std::fs::File;
+use std::io::{stdout, BufWriter};
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::Duration;
 
 fn main() -> ExitCode {
     let args = Args::parse();
@@ -28,7 +47,15 @@ fn main() -> ExitCode {
         return init::init(&fname);
     }
 
-    let lie = match lie::read(fname, args.unrestricted()) {
+    // Nothing is run when dry-running, listing tags or replaying, so any system command may be
+    // declared.
+    let policy = SystemPolicy::new(args.unrestricted(), args.allow());
+    let read_policy = if args.dry_run() || args.list_tags() || args.replay_cache() {
+        SystemPolicy::Unleashed
+    } else {
+        policy.clone()
+    };
+    let lie = match lie::read(&fname, args.unlimited(), read_policy) {
         Ok(t) => t,
         Err(e) => {
             eprintln!("{}", e);
@@ -38,10 +65,91 @@ fn main() -> ExitCode {
 
     if args.dry_run() {
         println!("{}", lie.dry_run());
+        let blocked = policy.blocked(lie.fibs());
+        if !blocked.is_empty() {
+            println!("\nblocked at this sandbox level:");
+            for cmd in blocked {
+                println!("! {cmd}");
+            }
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if args.list_tags() {
+        let pause = args.autoplay().unwrap_or_else(|| args.pause_delay());
+        println!("{}", lie.outline(pause));
         return ExitCode::SUCCESS;
     }
 
-    match Tale::from(lie).tell(&mut stdout().lock()) {
+    let mut tale = match Tale::from(lie).with_bounds(args.from(), args.to()) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let cache_fname = cache::path(&fname);
+    if args.prepare() {
+        return match tale
+            .prepare()
+            .map_err(Into::into)
+            .and_then(|cache| cache.save(&cache_fname))
+        {
+            Ok(()) => {
+                println!("saved system command output to {}", cache_fname.display());
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if args.replay_cache() {
+        tale = match Cache::load(&cache_fname).and_then(|cache| Ok(tale.with_cache(&cache)?)) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
+    }
+
+    if args.snapshot() {
+        let size = args.size().unwrap_or((80, 24));
+        return match snapshot::snapshot(&mut tale, size, args.snapshot_tags()) {
+            Ok(screen) => {
+                print!("{screen}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(cast_fname) = args.record() {
+        let size = args
+            .size()
+            .unwrap_or_else(|| terminal::size().unwrap_or((80, 24)));
+        return match record(tale, cast_fname, size, args.pause_delay()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut tale = tale
+        .with_autoplay(args.autoplay())
+        .with_typer(args.typer())
+        .with_loop(args.loop_wait(), args.rerun_systems())
+        .with_prefetch(args.prefetch());
+    match tale.tell(&mut stdout().lock(), &mut Terminal) {
         Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("{e}");
@@ -49,3 +157,19 @@ fn main() -> ExitCode {
         }
     }
 }
+
+fn record(
+    mut tale: Tale,
+    fname: &str,
+    (width, height): (u16, u16),
+    pause: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let mut cast = Cast::new(width, height);
+    if let Some(title) = tale.title() {
+        cast.set_title(title);
+    }
+    let mut input = Headless::new(pause, cast.clock(), (width, height));
+    tale.tell(&mut cast, &mut input)?;
+    cast.save(&mut BufWriter::new(File::create(fname)?))?;
+    Ok(())
+}
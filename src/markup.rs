@@ -0,0 +1,441 @@
+use crate::error::MendaxError;
+use crossterm::style::{Attribute, Color, ContentStyle};
+use phf::phf_map;
+use std::fmt::{self, Display};
+use std::ops::Add;
+
+static ATTRIBUTES: phf::Map<&'static str, Attribute> = phf_map! {
+    "bold" => Attribute::Bold,
+    "dim" => Attribute::Dim,
+    "italic" => Attribute::Italic,
+    "underline" => Attribute::Underlined,
+    "blink" => Attribute::SlowBlink,
+    "reverse" => Attribute::Reverse,
+    "hidden" => Attribute::Hidden,
+    "strike" => Attribute::CrossedOut,
+};
+
+/// The words which may be used in a style, other than `#rrggbb` and 256-colour numbers.
+const STYLE_NAMES: &[&str] = &[
+    "black",
+    "dark_grey",
+    "red",
+    "dark_red",
+    "green",
+    "dark_green",
+    "yellow",
+    "dark_yellow",
+    "blue",
+    "dark_blue",
+    "magenta",
+    "dark_magenta",
+    "cyan",
+    "dark_cyan",
+    "white",
+    "grey",
+    "on_<colour>",
+    "bold",
+    "dim",
+    "italic",
+    "underline",
+    "blink",
+    "reverse",
+    "hidden",
+    "strike",
+    "status",
+];
+
+/// A piece of marked-up text, in which `[bold green]` begins a style, `[/]` ends the latest one
+/// and `[[` is a literal `[`. Styles which include `status` are only used if the last command
+/// failed, and are red unless given another colour.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token<T = String> {
+    Text(T),
+    Begin(ContentStyle),
+    BeginOnFailure(ContentStyle),
+    End,
+}
+
+impl<T> Token<T> {
+    pub fn map_text<U, E>(self, f: impl FnOnce(T) -> Result<U, E>) -> Result<Token<U>, E> {
+        Ok(match self {
+            Self::Text(text) => Token::Text(f(text)?),
+            Self::Begin(style) => Token::Begin(style),
+            Self::BeginOnFailure(style) => Token::BeginOnFailure(style),
+            Self::End => Token::End,
+        })
+    }
+}
+
+pub fn parse(markup: &str) -> Result<Vec<Token>, MendaxError> {
+    let invalid = |reason| MendaxError::InvalidMarkup {
+        markup: markup.into(),
+        reason,
+    };
+
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut depth = 0;
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix('[') {
+            text.push('[');
+            rest = after;
+            continue;
+        }
+
+        let end = rest.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        if tag == "/" {
+            if depth == 0 {
+                return Err(invalid("'[/]' does not end a style"));
+            }
+            depth -= 1;
+            tokens.push(Token::End);
+        } else {
+            depth += 1;
+            let mut words: Vec<_> = tag.split_whitespace().collect();
+            let on_failure = words.contains(&"status");
+            words.retain(|word| *word != "status");
+            let mut style = style(&words.join(" "))?;
+            if on_failure {
+                style.foreground_color = style.foreground_color.or(Some(Color::Red));
+                tokens.push(Token::BeginOnFailure(style));
+            } else {
+                tokens.push(Token::Begin(style));
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+
+    Ok(tokens)
+}
+
+/// Parse the words of a style, such as `bold green on_black`.
+pub fn style(words: &str) -> Result<ContentStyle, MendaxError> {
+    let mut style = ContentStyle::new();
+    for word in words.split_whitespace() {
+        if let Some(attribute) = ATTRIBUTES.get(word) {
+            style.attributes.set(*attribute);
+        } else if let Some(background) = word.strip_prefix("on_") {
+            style.background_color = Some(colour(background)?);
+        } else {
+            style.foreground_color = Some(colour(word)?);
+        }
+    }
+    Ok(style)
+}
+
+/// The words of `style`, such that `style(&describe(s))` gives `s` back.
+pub fn describe(style: &ContentStyle) -> String {
+    let mut words: Vec<_> = STYLE_NAMES
+        .iter()
+        .filter(|name| {
+            ATTRIBUTES
+                .get(name)
+                .is_some_and(|attribute| style.attributes.has(*attribute))
+        })
+        .map(|name| name.to_string())
+        .collect();
+    if let Some(foreground) = style.foreground_color {
+        words.push(colour_name(foreground));
+    }
+    if let Some(background) = style.background_color {
+        words.push(format!("on_{}", colour_name(background)));
+    }
+    words.join(" ")
+}
+
+fn colour_name(colour: Color) -> String {
+    match colour {
+        Color::Rgb { r, g, b } => format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::AnsiValue(value) => value.to_string(),
+        colour => STYLE_NAMES
+            .iter()
+            .find(|name| Color::try_from(**name) == Ok(colour))
+            .map_or_else(|| format!("{colour:?}"), |name| name.to_string()),
+    }
+}
+
+fn colour(name: &str) -> Result<Color, MendaxError> {
+    let unknown = || MendaxError::UnknownColour(name.into(), STYLE_NAMES);
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(unknown)
+        };
+        if hex.len() != 6 {
+            return Err(unknown());
+        }
+        return Ok(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    if let Ok(value) = name.parse() {
+        return Ok(Color::AnsiValue(value));
+    }
+    Color::try_from(name).map_err(|()| unknown())
+}
+
+/// Every style which may be written as a single word, such as `bold` or `on_red`.
+pub fn words() -> impl Iterator<Item = (String, ContentStyle)> {
+    STYLE_NAMES
+        .iter()
+        .filter(|name| !["on_<colour>", "status"].contains(name))
+        .flat_map(|name| {
+            if ATTRIBUTES.contains_key(name) {
+                vec![name.to_string()]
+            } else {
+                vec![name.to_string(), format!("on_{name}")]
+            }
+        })
+        .map(|word| {
+            let style = style(&word).expect("style names are valid");
+            (word, style)
+        })
+}
+
+/// Render `tokens` with ANSI escapes, converting their text with `text`. Each piece of text is
+/// shown in the combination of all styles which surround it, ignoring those which depend on a
+/// command having failed unless it has.
+pub fn render<T>(tokens: &[Token<T>], failed: bool, text: impl FnMut(&T) -> String) -> String {
+    segments(tokens, failed, text)
+        .into_iter()
+        .map(|(style, t)| {
+            if style == ContentStyle::new() {
+                t
+            } else {
+                style.apply(t).to_string()
+            }
+        })
+        .collect()
+}
+
+/// The pieces of text in `tokens`, each with the style it is shown in.
+pub fn segments<T>(
+    tokens: &[Token<T>],
+    failed: bool,
+    mut text: impl FnMut(&T) -> String,
+) -> Vec<(ContentStyle, String)> {
+    let mut styles = vec![ContentStyle::new()];
+    let mut segments = vec![];
+    for token in tokens {
+        let current = styles[styles.len() - 1];
+        match token {
+            Token::Text(t) => segments.push((current, text(t))),
+            Token::Begin(style) => styles.push(combine(current, style)),
+            Token::BeginOnFailure(style) => styles.push(if failed {
+                combine(current, style)
+            } else {
+                current
+            }),
+            Token::End => {
+                if styles.len() > 1 {
+                    styles.pop();
+                }
+            }
+        }
+    }
+    segments
+}
+
+/// Text shown by a lie, which may be styled by marking it up.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Text(Vec<Token>);
+
+impl Text {
+    /// Parse marked-up text, as in `[green]ok[/]`.
+    pub fn parse(markup: &str) -> Result<Self, MendaxError> {
+        let tokens = parse(markup)?;
+        if tokens
+            .iter()
+            .any(|token| matches!(token, Token::BeginOnFailure(_)))
+        {
+            return Err(MendaxError::InvalidMarkup {
+                markup: markup.into(),
+                reason: "'status' may only be used in prompts",
+            });
+        }
+        Ok(Self(tokens))
+    }
+
+    /// This text shown in `style`, unless overridden by the styles within it.
+    pub fn styled(self, style: ContentStyle) -> Self {
+        let mut tokens = Vec::with_capacity(self.0.len() + 2);
+        tokens.push(Token::Begin(style));
+        tokens.extend(self.0);
+        tokens.push(Token::End);
+        Self(tokens)
+    }
+
+    /// Render the text with ANSI escapes.
+    pub fn render(&self) -> String {
+        render(&self.0, false, String::clone)
+    }
+
+    /// The pieces of the text, each with the style it is shown in.
+    pub fn segments(&self) -> Vec<(ContentStyle, String)> {
+        segments(&self.0, false, String::clone)
+    }
+
+    /// The text without its styles.
+    pub fn plain(&self) -> String {
+        self.segments().into_iter().map(|(_, text)| text).collect()
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        text.to_owned().into()
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        if text.is_empty() {
+            Self(vec![])
+        } else {
+            Self(vec![Token::Text(text)])
+        }
+    }
+}
+
+impl Add for Text {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+}
+
+/// Shows the text with its markup, so that it can be read without a terminal.
+impl Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.0 {
+            match token {
+                Token::Text(text) => write!(f, "{text}")?,
+                Token::Begin(style) => write!(f, "[{}]", describe(style))?,
+                Token::BeginOnFailure(style) => write!(f, "[status {}]", describe(style))?,
+                Token::End => write!(f, "[/]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `style` applied on top of `base`.
+fn combine(base: ContentStyle, style: &ContentStyle) -> ContentStyle {
+    ContentStyle {
+        foreground_color: style.foreground_color.or(base.foreground_color),
+        background_color: style.background_color.or(base.background_color),
+        underline_color: style.underline_color.or(base.underline_color),
+        attributes: base.attributes | style.attributes,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::style::Stylize;
+
+    #[test]
+    fn parse_markup() -> Result<(), MendaxError> {
+        assert_eq!(
+            parse("a [[b] [bold green on_#0000ff]c[/] d")?,
+            &[
+                Token::Text("a [b] ".into()),
+                Token::Begin(ContentStyle {
+                    foreground_color: Some(Color::Green),
+                    background_color: Some(Color::Rgb { r: 0, g: 0, b: 255 }),
+                    attributes: Attribute::Bold.into(),
+                    ..Default::default()
+                }),
+                Token::Text("c".into()),
+                Token::End,
+                Token::Text(" d".into()),
+            ]
+        );
+        assert_eq!(parse("[208]")?, &[Token::Begin(style("208")?)]);
+        for words in [
+            "bold underline dark_cyan on_#00ff7f",
+            "italic 208 on_black",
+            "",
+        ] {
+            assert_eq!(describe(&style(words)?), words);
+        }
+
+        assert_eq!(
+            parse("[bold").unwrap_err().to_string(),
+            "invalid markup \"[bold\": unclosed '['"
+        );
+        assert_eq!(
+            parse("a[/]").unwrap_err().to_string(),
+            "invalid markup \"a[/]\": '[/]' does not end a style"
+        );
+        assert!(parse("[bold pink]")
+            .unwrap_err()
+            .to_string()
+            .starts_with("unknown colour \"pink\", expected one of: black, "));
+
+        Ok(())
+    }
+
+    #[test]
+    fn render_nested() -> Result<(), MendaxError> {
+        let tokens = parse("a[green]b[bold]c[/]d[/]e")?;
+        assert_eq!(
+            render(&tokens, false, |t| t.to_uppercase()),
+            format!("A{}{}{}E", "B".green(), "C".green().bold(), "D".green())
+        );
+
+        let tokens = parse("[bold][status]a[/][status blue]b[/][/]")?;
+        assert_eq!(
+            render(&tokens, false, String::clone),
+            format!("{}{}", "a".bold(), "b".bold())
+        );
+        assert_eq!(
+            render(&tokens, true, String::clone),
+            format!("{}{}", "a".bold().red(), "b".bold().blue())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn text() -> Result<(), MendaxError> {
+        let text = Text::from("a ") + Text::parse("[green]b[/]")?.styled(style("bold")?);
+        assert_eq!(text.to_string(), "a [bold][green]b[/][/]");
+        assert_eq!(text.plain(), "a b");
+        assert_eq!(text.render(), format!("a {}", "b".green().bold()));
+        assert_eq!(
+            text.segments(),
+            &[
+                (ContentStyle::new(), "a ".into()),
+                (ContentStyle::new().green().bold(), "b".into())
+            ]
+        );
+        assert_eq!(Text::from("").to_string(), "");
+        assert_eq!(
+            Text::parse("[status]a[/]").unwrap_err().to_string(),
+            "invalid markup \"[status]a[/]\": 'status' may only be used in prompts"
+        );
+        assert!(words()
+            .any(|(word, style)| word == "on_red" && style.background_color == Some(Color::Red)));
+
+        Ok(())
+    }
+}
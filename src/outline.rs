@@ -0,0 +1,140 @@
+use crate::{fib::Fib, lie::Lie, tale::DEFAULT_SPEED};
+use std::time::Duration;
+
+pub trait Outline {
+    /// List the tags of a lie in order, estimating how long it takes to reach each, assuming
+    /// each pause lasts `pause`.
+    fn outline(&self, pause: Duration) -> String;
+}
+
+impl Outline for Lie {
+    fn outline(&self, pause: Duration) -> String {
+        let mut outliner = Outliner::new(pause);
+        outliner.outline(self.fibs(), 0);
+        outliner.add_mark("(end)", 0);
+        outliner.lines.join("\n")
+    }
+}
+
+struct Outliner {
+    lines: Vec<String>,
+    pause: Duration,
+    speed: f64,
+    elapsed: Duration,
+    commands: usize,
+}
+
+impl Outliner {
+    fn new(pause: Duration) -> Self {
+        Self {
+            lines: vec![],
+            pause,
+            speed: DEFAULT_SPEED,
+            elapsed: Duration::ZERO,
+            commands: 0,
+        }
+    }
+
+    fn outline(&mut self, fibs: &[Fib], depth: usize) {
+        for fib in fibs {
+            match fib {
+                Fib::Run { cmd, .. } => self.command(cmd),
+                Fib::System {
+                    apparent_cmd, cmd, ..
+                } => {
+                    self.command(apparent_cmd.as_ref().unwrap_or(cmd));
+                }
+                Fib::Screen { apparent_cmd, fibs } => {
+                    if let Some(apparent_cmd) = apparent_cmd {
+                        self.command(apparent_cmd);
+                    }
+                    if fibs.iter().any(|fib| matches!(fib, Fib::Tag { .. })) {
+                        let indent = "    ".repeat(depth);
+                        self.lines.push(match apparent_cmd {
+                            Some(apparent_cmd) => format!("{indent}(screen) {apparent_cmd}"),
+                            None => format!("{indent}(screen)"),
+                        });
+                    }
+                    self.outline(fibs, depth + 1);
+                    self.elapsed += self.pause;
+                }
+                Fib::Look { speed, .. } => {
+                    if let Some(speed) = speed {
+                        self.speed = *speed;
+                    }
+                }
+                Fib::Tag { name } => self.add_mark(&format!("(tag) {name}"), depth),
+                Fib::Sleep { duration } => self.elapsed += *duration,
+                Fib::Pause => self.elapsed += self.pause,
+                Fib::Enter { msg } => self.type_text(&msg.plain()),
+                Fib::Show { .. } | Fib::Git { .. } | Fib::Stop | Fib::Clear => {}
+            }
+        }
+    }
+
+    fn command(&mut self, cmd: &str) {
+        self.commands += 1;
+        self.elapsed += self.pause;
+        if !cmd.trim().is_empty() {
+            self.type_text(cmd);
+        }
+    }
+
+    fn type_text(&mut self, text: &str) {
+        self.elapsed += Duration::from_secs_f64(self.speed * text.chars().count() as f64);
+        self.elapsed += self.pause;
+    }
+
+    fn add_mark(&mut self, mark: &str, depth: usize) {
+        self.lines.push(format!(
+            "{}{mark}: {} command{}, ~{}",
+            "    ".repeat(depth),
+            self.commands,
+            if self.commands == 1 { "" } else { "s" },
+            pretty_duration::pretty_duration(&Duration::from_secs(self.elapsed.as_secs()), None),
+        ));
+        self.commands = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lie;
+    use indoc::indoc;
+
+    #[test]
+    fn outline() {
+        assert_eq!(
+            lie::test::test_script(
+                false,
+                r#"
+                    lie.look(#{ speed: 0.5 });
+                    lie.run("ls");
+                    lie.tag("foo");
+                    lie.run("");
+                    lie.sleep(3000);
+                    lie.screen("man foo", |lie| {
+                        lie.show("FOO(1)");
+                        lie.tag("bar");
+                        lie.pause();
+                    });
+                    lie.screen(|lie| {
+                        lie.pause();
+                    });
+                    lie.tag("baz");
+                "#
+            )
+            .unwrap()
+            .outline(Duration::from_secs(1)),
+            indoc! {"
+                (tag) foo: 1 command, ~3s
+                (screen) man foo
+                    (tag) bar: 2 commands, ~12s
+                (tag) baz: 0 commands, ~16s
+                (end): 0 commands, ~16s
+            "}
+            .trim(),
+        );
+    }
+}
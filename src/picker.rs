@@ -0,0 +1,248 @@
+use crate::error::MendaxError;
+use crate::input::Input;
+use crossterm::{
+    cursor::{Hide, MoveToNextLine, MoveUp, RestorePosition, SavePosition, Show},
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{Clear, ClearType},
+};
+use std::error::Error;
+use std::io::Write;
+
+/// The most matching tags shown at once.
+const MAX_SHOWN: usize = 5;
+
+/// Whether the picker can be drawn below the cursor without scrolling the screen, which is
+/// assumed not to be the case if the cursor position or terminal size is unknown.
+pub fn fits<I: Input>(input: &mut I) -> bool {
+    match (input.cursor_position(), input.terminal_size()) {
+        (Some((_, row)), Some((_, rows))) => row + MAX_SHOWN as u16 + 1 < rows,
+        _ => false,
+    }
+}
+
+/// Let the presenter choose a tag by typing part of its name, returning the chosen tag's
+/// position or `None` if the choice was cancelled. The picker is drawn below the cursor and
+/// erased once done.
+pub fn pick<W: Write, I: Input>(
+    tags: &[(String, usize)],
+    out: &mut W,
+    input: &mut I,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    let rows = MAX_SHOWN as u16 + 1;
+    queue!(
+        out,
+        Hide,
+        Print("\n".repeat(rows as usize)),
+        MoveUp(rows),
+        SavePosition
+    )?;
+
+    let mut picker = Picker {
+        tags,
+        query: String::new(),
+        selected: 0,
+    };
+    let picked = loop {
+        picker.draw(out)?;
+
+        let matches = picker.matches();
+        match input.read()? {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => {
+                execute!(out, RestorePosition, Clear(ClearType::FromCursorDown), Show)?;
+                return Err(Box::new(MendaxError::KeyboardInterrupt));
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc, ..
+            }) => break None,
+            Event::Key(KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            }) => {
+                if let Some((_, pc)) = matches.get(picker.selected) {
+                    break Some(*pc);
+                }
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Up | KeyCode::BackTab,
+                ..
+            })
+            | Event::Key(KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => picker.selected = picker.selected.saturating_sub(1),
+            Event::Key(KeyEvent {
+                code: KeyCode::Down | KeyCode::Tab,
+                ..
+            })
+            | Event::Key(KeyEvent {
+                code: KeyCode::Char('n'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => picker.selected = (picker.selected + 1).min(matches.len().saturating_sub(1)),
+            Event::Key(KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            }) => {
+                picker.query.pop();
+                picker.selected = 0;
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers,
+                ..
+            }) if !modifiers.contains(KeyModifiers::CONTROL) => {
+                picker.query.push(c);
+                picker.selected = 0;
+            }
+            _ => {}
+        }
+    };
+
+    execute!(
+        out,
+        RestorePosition,
+        Clear(ClearType::FromCursorDown),
+        RestorePosition,
+        Show
+    )?;
+    Ok(picked)
+}
+
+struct Picker<'tags> {
+    tags: &'tags [(String, usize)],
+    query: String,
+    selected: usize,
+}
+
+impl<'tags> Picker<'tags> {
+    /// The tags which match the query, in document order.
+    fn matches(&self) -> Vec<&'tags (String, usize)> {
+        self.tags
+            .iter()
+            .filter(|(name, _)| fuzzy_match(&self.query, name))
+            .collect()
+    }
+
+    fn draw<W: Write>(&self, out: &mut W) -> Result<(), Box<dyn Error>> {
+        queue!(
+            out,
+            RestorePosition,
+            Clear(ClearType::FromCursorDown),
+            MoveToNextLine(1),
+            Print(format!("mendax: jump to: {}", self.query)),
+        )?;
+
+        let matches = self.matches();
+        if matches.is_empty() {
+            queue!(
+                out,
+                MoveToNextLine(1),
+                SetAttribute(Attribute::Dim),
+                Print("  (no matching tags)"),
+                SetAttribute(Attribute::Reset),
+            )?;
+        }
+
+        let first = (self.selected + 1).saturating_sub(MAX_SHOWN);
+        for (i, (name, _)) in matches.iter().enumerate().skip(first).take(MAX_SHOWN) {
+            queue!(out, MoveToNextLine(1))?;
+            if i == self.selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(format!("> {name}")),
+                    SetAttribute(Attribute::Reset),
+                )?;
+            } else {
+                queue!(out, Print(format!("  {name}")))?;
+            }
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Whether the characters of `query` appear in order in `name`, ignoring case.
+fn fuzzy_match(query: &str, name: &str) -> bool {
+    let mut name = name.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| name.any(|c| c == q))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::input::test::Scripted;
+
+    fn tags() -> Vec<(String, usize)> {
+        ["intro", "build", "deploy", "debug"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_owned(), i * 10))
+            .collect()
+    }
+
+    #[test]
+    fn fuzzy() {
+        assert!(fuzzy_match("", "deploy"));
+        assert!(fuzzy_match("dpy", "deploy"));
+        assert!(fuzzy_match("DEP", "deploy"));
+        assert!(!fuzzy_match("yd", "deploy"));
+    }
+
+    #[test]
+    fn pick_tag() -> Result<(), Box<dyn Error>> {
+        let tags = tags();
+        let mut out = vec![];
+
+        assert_eq!(
+            pick(&tags, &mut out, &mut Scripted::keys("de\n"))?,
+            Some(20)
+        );
+        assert_eq!(
+            pick(
+                &tags,
+                &mut out,
+                &mut Scripted::keys("de").then(KeyCode::Down).then_keys("\n")
+            )?,
+            Some(30)
+        );
+        assert_eq!(
+            pick(
+                &tags,
+                &mut out,
+                &mut Scripted::keys("x")
+                    .then(KeyCode::Enter)
+                    .then(KeyCode::Backspace)
+                    .then_keys("bld\n")
+            )?,
+            Some(10)
+        );
+        assert_eq!(pick(&tags, &mut out, &mut Scripted::keys("in\x1b"))?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_no_trace() -> Result<(), Box<dyn Error>> {
+        let mut term = vt100::Parser::new(24, 40, 0);
+        term.process(b"one\r\ntwo\r\nthree$ ");
+        let before = term.screen().contents();
+
+        pick(&tags(), &mut term, &mut Scripted::keys("dep\n"))?;
+
+        assert_eq!(term.screen().contents(), before);
+        assert_eq!(term.screen().cursor_position(), (2, 7));
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::fib::{Fib, SystemOptions};
+
+/// Characters with special meaning to the shell, which could be used to run more than an
+/// allowed command.
+const SHELL_SYNTAX: &[char] = &[
+    ';', '&', '|', '<', '>', '$', '`', '(', ')', '{', '}', '[', ']', '*', '?', '\\', '\'', '"',
+    '\n', '\r',
+];
+
+/// Which system commands a lie may run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SystemPolicy {
+    #[default]
+    Forbid,
+    /// Allow commands which start with one of the given patterns, use no shell syntax and leave
+    /// their environment as it is.
+    Allow(Vec<Pattern>),
+    Unleashed,
+}
+
+impl SystemPolicy {
+    pub fn new(unleashed: bool, allowed: &[String]) -> Self {
+        if unleashed {
+            Self::Unleashed
+        } else if !allowed.is_empty() {
+            Self::Allow(
+                allowed
+                    .iter()
+                    .map(|pattern| Pattern::new(pattern))
+                    .collect(),
+            )
+        } else {
+            Self::Forbid
+        }
+    }
+
+    pub fn permits(&self, cmd: &str, options: &SystemOptions) -> bool {
+        match self {
+            Self::Forbid => false,
+            Self::Allow(patterns) => {
+                // Variables such as `PATH` or `LD_PRELOAD` could make an allowed command run
+                // something else entirely.
+                options.env.is_empty()
+                    && !options.clear_env
+                    && !cmd.contains(SHELL_SYNTAX)
+                    && patterns.iter().any(|pattern| pattern.matches(cmd))
+            }
+            Self::Unleashed => true,
+        }
+    }
+
+    /// The system commands in `fibs` which this policy does not permit.
+    pub fn blocked<'fib>(&self, fibs: &'fib [Fib]) -> Vec<&'fib str> {
+        fibs.iter()
+            .flat_map(|fib| match fib {
+                Fib::System { cmd, options, .. } if !self.permits(cmd, options) => vec![&cmd[..]],
+                Fib::Screen { fibs, .. } => self.blocked(fibs),
+                _ => vec![],
+            })
+            .collect()
+    }
+}
+
+/// The leading words of a command, where `*` stands for any one word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern(Vec<String>);
+
+impl Pattern {
+    fn new(pattern: &str) -> Self {
+        Self(pattern.split_whitespace().map(ToOwned::to_owned).collect())
+    }
+
+    fn matches(&self, cmd: &str) -> bool {
+        let mut words = cmd.split_whitespace();
+        !self.0.is_empty()
+            && self.0.iter().all(|expected| {
+                words
+                    .next()
+                    .is_some_and(|word| expected == "*" || expected == word)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lie;
+    use std::error::Error;
+
+    #[test]
+    fn permits() {
+        let none = SystemOptions::default();
+        let policy = SystemPolicy::new(
+            false,
+            &["kubectl get".into(), "ls".into(), "git * --help".into()],
+        );
+
+        assert!(policy.permits("kubectl get pods", &none));
+        assert!(policy.permits("  kubectl   get pods -n demo", &none));
+        assert!(policy.permits("ls", &none));
+        assert!(policy.permits("git log --help", &none));
+        assert!(!policy.permits("kubectl delete pods", &none));
+        assert!(!policy.permits("kubectl", &none));
+        assert!(!policy.permits("lsblk", &none));
+        assert!(!policy.permits("git log", &none));
+        assert!(!policy.permits("kubectl get pods; rm -rf ~", &none));
+        assert!(!policy.permits("kubectl get $(rm -rf ~)", &none));
+        assert!(!policy.permits("ls > /etc/passwd", &none));
+        assert!(!policy.permits(
+            "kubectl get pods",
+            &SystemOptions {
+                env: vec![("PATH".into(), "/tmp/evil".into())],
+                ..Default::default()
+            }
+        ));
+        assert!(!policy.permits(
+            "kubectl get pods",
+            &SystemOptions {
+                clear_env: true,
+                ..Default::default()
+            }
+        ));
+        assert!(policy.permits(
+            "kubectl get pods",
+            &SystemOptions {
+                timeout: Some(std::time::Duration::from_secs(1)),
+                cwd: Some("demo".into()),
+                ..Default::default()
+            }
+        ));
+
+        assert!(!SystemPolicy::new(false, &[]).permits("ls", &none));
+        assert!(SystemPolicy::new(true, &[]).permits("ls | wc -l", &none));
+    }
+
+    #[test]
+    fn blocked() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            true,
+            r#"
+                lie.system("kubectl get pods");
+                lie.system("kubectl delete pods");
+                lie.system("kubectl get nodes", #{ env: #{ LD_PRELOAD: "/tmp/evil.so" } });
+                lie.system("kubectl get pods", #{ clear_env: true });
+                lie.screen(|lie| {
+                    lie.system("less README.md", "cat README.md");
+                });
+            "#,
+        )?;
+
+        let policy = SystemPolicy::new(false, &["kubectl get".into(), "less".into()]);
+        assert_eq!(
+            policy.blocked(lie.fibs()),
+            &[
+                "kubectl delete pods",
+                "kubectl get nodes",
+                "kubectl get pods",
+                "cat README.md"
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::MendaxError;
+use crate::markup::{self, Token};
+use std::fmt::{self, Display};
+use unicode_width::UnicodeWidthStr;
+
+/// The prompt `mendax` shows unless told otherwise.
+pub const DEFAULT_PRESET: &str = "bash";
+
+/// Prompts which may be used by name, with the templates they stand for.
+const PRESETS: &[(&str, &str)] = &[
+    (
+        "bash",
+        "[bold green]{user}@{host}[/]:[bold blue]{cwd}[/]{git}[status]{exit}$[/] ",
+    ),
+    ("zsh", "{user}@{host} {cwd}{git}[status]{exit} %[/] "),
+    ("fish", "{user}@{host} [green]{cwd}[/]{git}[status]{exit}>[/] "),
+    ("powershell", "PS {cwd}{git}[status]{exit}>[/] "),
+    (
+        "starship",
+        "[bold cyan]{cwd}[/][bold magenta]{git}[/]\n[bold green][status]\u{276f}[/][/] ",
+    ),
+    (
+        "root",
+        "[bold red]root@{host}[/]:[bold blue]{cwd}[/]{git}[status]{exit}#[/] ",
+    ),
+    (
+        "powerline",
+        "[black on_blue] {user}@{host} [/][blue on_green]\u{e0b0}[/][black on_green] {cwd} [/][green]\u{e0b0}[/] ",
+    ),
+];
+
+const PLACEHOLDERS: &[&str] = &["cwd", "exit", "git", "git_branch", "host", "time", "user"];
+
+/// Something which may be shown in a prompt, written as `{user}`, `{cwd}` and so on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placeholder {
+    User,
+    Host,
+    Cwd,
+    /// The branch and status of the repository, as in ` (main* ↑2)`, if in one.
+    Git,
+    GitBranch,
+    /// The exit status of the last command, as in ` [2]`, if it failed.
+    Exit,
+    Time,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Piece {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A prompt template, in which placeholders are filled in each time it is shown. Templates may
+/// be marked up with styles, span several lines and `{{` and `}}` are literal braces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prompt {
+    source: String,
+    tokens: Vec<Token<Vec<Piece>>>,
+}
+
+impl Prompt {
+    /// Parse the prompt `template`, or use the preset of that name if there is one.
+    pub fn new(template: &str) -> Result<Self, MendaxError> {
+        let expanded = PRESETS
+            .iter()
+            .find(|(name, _)| *name == template)
+            .map_or(template, |(_, preset)| preset);
+        let tokens = markup::parse(expanded)?
+            .into_iter()
+            .map(|token| token.map_text(|text| pieces(&text)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            source: template.into(),
+            tokens,
+        })
+    }
+
+    /// Render the prompt, filling in its placeholders with `fill`. Styles marked with `status`
+    /// are only used if the last command `failed`.
+    pub fn render(&self, failed: bool, mut fill: impl FnMut(Placeholder) -> String) -> String {
+        markup::render(&self.tokens, failed, |pieces| {
+            pieces
+                .iter()
+                .map(|piece| match piece {
+                    Piece::Literal(text) => text.clone(),
+                    Piece::Placeholder(placeholder) => fill(*placeholder),
+                })
+                .collect()
+        })
+    }
+
+    /// The number of columns the prompt takes up on its last line.
+    pub fn width(&self, mut fill: impl FnMut(Placeholder) -> String) -> usize {
+        let plain: String = self
+            .tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Text(pieces) => Some(pieces),
+                _ => None,
+            })
+            .flatten()
+            .map(|piece| match piece {
+                Piece::Literal(text) => text.clone(),
+                Piece::Placeholder(placeholder) => fill(*placeholder),
+            })
+            .collect();
+        plain.rsplit('\n').next().unwrap_or_default().width()
+    }
+}
+
+impl Default for Prompt {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRESET).expect("presets are valid")
+    }
+}
+
+impl Display for Prompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn pieces(text: &str) -> Result<Vec<Piece>, MendaxError> {
+    let mut pieces = vec![];
+    let mut literal = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let (name, rest) =
+                    chars
+                        .as_str()
+                        .split_once('}')
+                        .ok_or_else(|| MendaxError::InvalidMarkup {
+                            markup: text.into(),
+                            reason: "unclosed '{'",
+                        })?;
+                let placeholder = match name {
+                    "user" => Placeholder::User,
+                    "host" => Placeholder::Host,
+                    "cwd" => Placeholder::Cwd,
+                    "git" => Placeholder::Git,
+                    "git_branch" => Placeholder::GitBranch,
+                    "exit" => Placeholder::Exit,
+                    "time" => Placeholder::Time,
+                    _ => {
+                        return Err(MendaxError::UnknownPlaceholder {
+                            name: name.into(),
+                            expected: PLACEHOLDERS,
+                        })
+                    }
+                };
+                chars = rest.chars();
+                if !literal.is_empty() {
+                    pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                }
+                pieces.push(Piece::Placeholder(placeholder));
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+    Ok(pieces)
+}
+
+/// The current local time as `HH:MM:SS`.
+pub fn time() -> String {
+    // SAFETY: `localtime_r` only writes to the given `tm`.
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        tm
+    };
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::style::Stylize;
+
+    fn fill(placeholder: Placeholder) -> String {
+        format!("{placeholder:?}")
+    }
+
+    #[test]
+    fn template() -> Result<(), MendaxError> {
+        assert_eq!(
+            Prompt::new("{{{user}}} [bold]{cwd}[/] {{}}%")?.render(false, fill),
+            format!("{{User}} {} {{}}%", "Cwd".bold())
+        );
+        assert_eq!(
+            Prompt::new("{git_branch}{exit}{time}{host}")?.render(false, fill),
+            "GitBranchExitTimeHost"
+        );
+        assert_eq!(
+            Prompt::new("{home}").unwrap_err().to_string(),
+            "unknown placeholder {home}, expected one of: cwd, exit, git, git_branch, host, time, user"
+        );
+        assert_eq!(
+            Prompt::new("{user").unwrap_err().to_string(),
+            "invalid markup \"{user\": unclosed '{'"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn presets() -> Result<(), MendaxError> {
+        for (name, _) in PRESETS {
+            Prompt::new(name)?;
+        }
+        assert_eq!(
+            Prompt::new("zsh")?.render(false, fill),
+            "User@Host CwdGitExit % "
+        );
+        assert_eq!(Prompt::new("zsh")?.to_string(), "zsh");
+        assert_eq!(Prompt::new("starship")?.width(fill), 2);
+        assert_eq!(Prompt::new("powerline")?.width(fill), 19);
+        assert_eq!(
+            Prompt::default().render(false, fill),
+            format!(
+                "{}:{}GitExit$ ",
+                "User@Host".bold().green(),
+                "Cwd".bold().blue()
+            )
+        );
+        assert_eq!(
+            Prompt::default().render(true, fill),
+            format!(
+                "{}:{}Git{} ",
+                "User@Host".bold().green(),
+                "Cwd".bold().blue(),
+                "Exit$".red()
+            )
+        );
+
+        Ok(())
+    }
+}
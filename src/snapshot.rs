@@ -0,0 +1,114 @@
+use crate::input::{Clock, Headless};
+use crate::tale::Tale;
+use std::error::Error;
+use std::time::Duration;
+use vt100::Parser;
+
+/// Play a tale into a virtual terminal and return what the audience would see at the end,
+/// optionally preceded by what they would see at each tag.
+pub fn snapshot(
+    tale: &mut Tale,
+    (width, height): (u16, u16),
+    at_tags: bool,
+) -> Result<String, Box<dyn Error>> {
+    let mut term = Parser::new(height, width, 0);
+    let mut input = Headless::new(Duration::ZERO, Clock::default(), (width, height));
+
+    let mut sections = vec![];
+    tale.tell_observed(&mut term, &mut input, |tag, term| {
+        if at_tags {
+            sections.push(format!("(tag) {tag}\n{}", contents(term)));
+        }
+    })?;
+    sections.push(format!("(end)\n{}", contents(&term)));
+
+    Ok(sections.join("\n"))
+}
+
+fn contents(term: &Parser) -> String {
+    let screen = term.screen();
+    let (_, width) = screen.size();
+    let mut rows: Vec<_> = screen
+        .rows(0, width)
+        .map(|row| row.trim_end().to_owned())
+        .collect();
+    while rows.last().map(|row| row.is_empty()).unwrap_or_default() {
+        rows.pop();
+    }
+    rows.into_iter().map(|row| row + "\n").collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lie;
+    use indoc::indoc;
+
+    #[test]
+    fn final_screen() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            false,
+            r#"
+                lie.run("echo foo", "foo\n");
+                lie.cd("/tmp");
+                lie.run("ls", ["bar\n", "baz\n"]);
+            "#,
+        )?;
+
+        assert_eq!(
+            snapshot(&mut Tale::from(lie), (40, 10), false)?,
+            indoc! {"
+                (end)
+                ubuntu@ubuntu:~$ echo foo
+                foo
+                ubuntu@ubuntu:~$ cd /tmp
+                ubuntu@ubuntu:/tmp$ ls
+                bar
+                baz
+                ubuntu@ubuntu:/tmp$
+            "}
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tags_and_screens() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            false,
+            r#"
+                lie.run("echo foo", "foo\n");
+                lie.tag("before");
+                lie.screen("man foo", |lie| {
+                    lie.tag("inside");
+                    lie.show("FOO(1)\n");
+                });
+                lie.tag("after");
+            "#,
+        )?;
+
+        assert_eq!(
+            snapshot(&mut Tale::from(lie), (40, 10), true)?,
+            indoc! {"
+                (tag) before
+                ubuntu@ubuntu:~$ echo foo
+                foo
+
+                (tag) inside
+
+                (tag) after
+                ubuntu@ubuntu:~$ echo foo
+                foo
+                ubuntu@ubuntu:~$ man foo
+
+                (end)
+                ubuntu@ubuntu:~$ echo foo
+                foo
+                ubuntu@ubuntu:~$ man foo
+                ubuntu@ubuntu:~$
+            "}
+        );
+
+        Ok(())
+    }
+}
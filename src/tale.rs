@@ -1,39 +1,70 @@
+use crate::cache::Cache;
 use crate::error::MendaxError;
-use crate::fib::Fib;
+use crate::fib::{Fib, Output, SystemOptions};
+use crate::input::{self, Input};
 use crate::lie::Lie;
+use crate::markup::Text;
+use crate::picker;
+use crate::prompt::{self, Placeholder, Prompt};
+use crate::typos;
 use crossterm::{
-    cursor::{DisableBlinking, EnableBlinking, Hide, MoveTo, RestorePosition, SavePosition, Show},
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    style::{Attribute, Print, SetAttribute, Stylize},
+    cursor::{
+        DisableBlinking, EnableBlinking, Hide, MoveLeft, MoveRight, MoveTo, RestorePosition,
+        SavePosition, Show,
+    },
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Attribute, ContentStyle, Print, SetAttribute, SetStyle},
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
 };
+use lazy_static::lazy_static;
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use rand::Rng;
-use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::fmt::Display;
-use std::io::{self, Read, StdoutLock, Write};
-use std::{thread, time::Duration};
-use subprocess::{Exec, PopenError, Redirection};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref DEFAULT_PROMPT: Prompt = Prompt::default();
+}
+
+/// The default number of seconds between keystrokes when typing.
+pub const DEFAULT_SPEED: f64 = 0.040;
 
 #[derive(Debug)]
 pub struct Tale {
     steps: Vec<Step>,
-    tags: HashMap<String, usize>,
-    num_systems: usize,
+    tags: Vec<(String, usize)>,
+    system_outputs: Vec<SystemCacheEntry>,
+    cached_systems: bool,
+    prefetch: Option<Duration>,
+    typer: Option<usize>,
+    autoplay: Option<Duration>,
+    loop_wait: Option<Duration>,
+    rerun_systems: bool,
+    start: usize,
+    end: Option<usize>,
 }
 
 impl From<Lie> for Tale {
     fn from(lie: Lie) -> Self {
         let mut steps = vec![];
-        let mut tags = HashMap::new();
+        let mut tags = vec![];
         let mut num_systems = 0;
         let mut add_final_prompt = true;
+        let dir = lie.dir().to_owned();
         Self::flatten(
             &mut steps,
             &mut tags,
             &mut num_systems,
             &mut add_final_prompt,
+            &dir,
             lie.into_fibs(),
         );
 
@@ -47,47 +78,194 @@ impl From<Lie> for Tale {
         Self {
             steps,
             tags,
-            num_systems,
+            system_outputs: vec![SystemCacheEntry::default(); num_systems],
+            cached_systems: false,
+            prefetch: None,
+            typer: None,
+            autoplay: None,
+            loop_wait: None,
+            rerun_systems: false,
+            start: 0,
+            end: None,
         }
     }
 }
 
 impl Tale {
+    /// Resolve pauses automatically after `delay`, unless a key is pressed to hold playback.
+    pub fn with_autoplay(mut self, delay: Option<Duration>) -> Self {
+        self.autoplay = delay;
+        self
+    }
+
+    /// Restart the tale after `wait` once it ends, reusing system command output unless
+    /// `rerun_systems` is set.
+    pub fn with_loop(mut self, wait: Option<Duration>, rerun_systems: bool) -> Self {
+        self.loop_wait = wait;
+        self.rerun_systems = rerun_systems;
+        self
+    }
+
+    /// Type commands as the presenter presses keys, revealing `chars_per_key` characters at a
+    /// time, rather than on a timer.
+    pub fn with_typer(mut self, chars_per_key: Option<usize>) -> Self {
+        self.typer = chars_per_key;
+        self
+    }
+
+    /// Run system commands in the background ahead of when they are reached, then show their
+    /// output a line at a time every `line_delay`.
+    pub fn with_prefetch(mut self, line_delay: Option<Duration>) -> Self {
+        self.prefetch = line_delay;
+        self
+    }
+
+    /// Begin telling the tale at tag `from` and stop at tag `to`.
+    pub fn with_bounds(
+        mut self,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<Self, MendaxError> {
+        let start = from.map(|from| self.tag(from)).transpose()?;
+        let end = to.map(|to| self.tag(to)).transpose()?;
+        if let (Some(from), Some(to), Some(start), Some(end)) = (from, to, start, end) {
+            if end <= start {
+                return Err(MendaxError::TagOrder {
+                    from: from.into(),
+                    to: to.into(),
+                });
+            }
+        }
+
+        self.start = start.unwrap_or_default();
+        self.end = end;
+        Ok(self)
+    }
+
+    /// Replay system command output from `cache` rather than running the commands.
+    pub fn with_cache(mut self, cache: &Cache) -> Result<Self, MendaxError> {
+        self.system_outputs =
+            self.systems()
+                .into_iter()
+                .map(|system| {
+                    let cached = cache.get(system.id(), system.cmd()).ok_or_else(|| {
+                        MendaxError::Uncached {
+                            cmd: system.cmd().into(),
+                        }
+                    })?;
+                    Ok(SystemCacheEntry {
+                        output: Some(cached.output().to_vec()),
+                        requires_newline: cached.requires_newline(),
+                        status: cached.status(),
+                    })
+                })
+                .collect::<Result<_, MendaxError>>()?;
+        self.cached_systems = true;
+        Ok(self)
+    }
+
+    /// Run every system command ahead of time, collecting their output.
+    pub fn prepare(&self) -> Result<Cache, MendaxError> {
+        let mut cache = Cache::default();
+        for system in self.systems() {
+            let mut entry = SystemCacheEntry::default();
+            // Interactive commands need the presenter, so are left out of the cache.
+            if !system.interactive() {
+                system.capture(None, &mut entry)?;
+            }
+            cache.push(
+                system.cmd(),
+                entry.output().unwrap_or_default(),
+                entry.requires_newline(),
+                entry.status(),
+            );
+        }
+        Ok(cache)
+    }
+
+    fn systems(&self) -> Vec<&System> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::System(system) => Some(system),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn tag(&self, name: &str) -> Result<usize, MendaxError> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, pc)| *pc)
+            .ok_or_else(|| MendaxError::UnknownTag {
+                name: name.into(),
+                known: self.tags.iter().map(|(tag, _)| tag.clone()).collect(),
+            })
+    }
+
     fn flatten(
         steps: &mut Vec<Step>,
-        tags: &mut HashMap<String, usize>,
+        tags: &mut Vec<(String, usize)>,
         num_systems: &mut usize,
         add_final_prompt: &mut bool,
+        dir: &Path,
         fibs: Vec<Fib>,
     ) {
         for fib in fibs {
             match fib {
-                Fib::Run { cmd, result } => {
+                Fib::Run {
+                    cmd,
+                    result,
+                    options,
+                } => {
                     steps.push(Step::Ps1);
                     steps.push(Step::ShowCursor);
                     steps.push(Step::Pause);
                     if !cmd.trim().is_empty() {
-                        steps.push(Step::Type(cmd));
+                        steps.push(Step::Type(cmd, options.typos, ContentStyle::new()));
                         steps.push(Step::Pause);
                     }
                     steps.push(Step::Show("\r\n".into()));
                     steps.push(Step::HideCursor);
-                    for line in result {
-                        steps.push(Step::Show(Self::sanitise(&line)));
+                    for chunk in result {
+                        steps.push(match chunk {
+                            Output::Stdout(text) => Step::Show(Self::sanitise(&text.render())),
+                            Output::Stderr(text) => Step::ShowStderr(text),
+                        });
                     }
+                    steps.push(Step::SetStatus(options.status));
                 }
-                Fib::Show { text } => steps.push(Step::Show(Self::sanitise(&text))),
-                Fib::System { apparent_cmd, cmd } => {
+                Fib::Show { text } => steps.push(Step::Show(Self::sanitise(&text.render()))),
+                Fib::System {
+                    apparent_cmd,
+                    cmd,
+                    mut options,
+                } => {
+                    options.fallback = options
+                        .fallback
+                        .map(|lines| lines.iter().map(|line| Self::sanitise(line)).collect());
                     steps.push(Step::ShowCursor);
                     steps.push(Step::Ps1);
                     steps.push(Step::Pause);
-                    steps.push(Step::Type(Self::sanitise(
-                        &apparent_cmd.unwrap_or_else(|| cmd.clone()),
-                    )));
+                    steps.push(Step::Type(
+                        Self::sanitise(&apparent_cmd.unwrap_or_else(|| cmd.clone())),
+                        options.typos,
+                        ContentStyle::new(),
+                    ));
                     steps.push(Step::Pause);
                     steps.push(Step::Show("\r\n".into()));
                     steps.push(Step::HideCursor);
-                    steps.push(Step::System(System::new(cmd, *num_systems)));
+                    // The command sets the real status once it has run, which it may not if it
+                    // is jumped over.
+                    steps.push(Step::SetStatus(0));
+                    let options = SystemOptions {
+                        cwd: options
+                            .cwd
+                            .map(|cwd| dir.join(cwd).to_string_lossy().into_owned()),
+                        ..options
+                    };
+                    steps.push(Step::System(System::new(cmd, *num_systems, options)));
                     *num_systems += 1;
                 }
                 Fib::Screen {
@@ -99,14 +277,18 @@ impl Tale {
                         steps.push(Step::ShowCursor);
                         steps.push(Step::Pause);
                         if !apparent_cmd.trim().is_empty() {
-                            steps.push(Step::Type(Self::sanitise(&apparent_cmd)));
+                            steps.push(Step::Type(
+                                Self::sanitise(&apparent_cmd),
+                                None,
+                                ContentStyle::new(),
+                            ));
                             steps.push(Step::Pause);
                         }
                         steps.push(Step::Show("\r\n".into()));
                         steps.push(Step::HideCursor);
                     }
                     steps.push(Step::ScreenOpen);
-                    Self::flatten(steps, tags, num_systems, add_final_prompt, child);
+                    Self::flatten(steps, tags, num_systems, add_final_prompt, dir, child);
                     steps.push(Step::ShowCursor);
                     steps.push(Step::Pause);
                     steps.push(Step::ScreenClose);
@@ -118,6 +300,10 @@ impl Tale {
                     host,
                     user,
                     final_prompt,
+                    typos,
+                    prompt,
+                    right_prompt,
+                    stderr,
                 } => {
                     if let Some(title) = title {
                         steps.push(Step::SetTitle(title));
@@ -134,18 +320,55 @@ impl Tale {
                     if let Some(speed) = speed {
                         steps.push(Step::SetSpeed(speed));
                     }
+                    if let Some(typos) = typos {
+                        steps.push(Step::SetTypos(typos));
+                    }
+                    if let Some(prompt) = prompt {
+                        steps.push(Step::SetPrompt(prompt));
+                    }
+                    if let Some(right_prompt) = right_prompt {
+                        steps.push(Step::SetRightPrompt(right_prompt));
+                    }
+                    if let Some(stderr) = stderr {
+                        steps.push(Step::SetStderrStyle(stderr));
+                    }
                     if let Some(final_prompt) = final_prompt {
                         *add_final_prompt = final_prompt;
                     }
                 }
+                Fib::Git {
+                    branch,
+                    dirty,
+                    ahead,
+                    behind,
+                } => {
+                    if let Some(branch) = branch {
+                        steps.push(Step::SetGitBranch(branch));
+                    }
+                    if let Some(dirty) = dirty {
+                        steps.push(Step::SetGitDirty(dirty));
+                    }
+                    if let Some(ahead) = ahead {
+                        steps.push(Step::SetGitAhead(ahead));
+                    }
+                    if let Some(behind) = behind {
+                        steps.push(Step::SetGitBehind(behind));
+                    }
+                }
                 Fib::Tag { name } => {
-                    tags.insert(name, steps.len());
+                    tags.push((name, steps.len()));
                 }
                 Fib::Sleep { duration } => steps.push(Step::Sleep(duration)),
                 Fib::Pause => steps.push(Step::Pause),
                 Fib::Stop => steps.push(Step::Stop),
                 Fib::Enter { msg } => {
-                    steps.push(Step::Type(Self::sanitise(&msg)));
+                    let mut segments = msg.segments();
+                    if segments.is_empty() {
+                        segments.push((ContentStyle::new(), String::new()));
+                    }
+                    for (pen, text) in segments {
+                        steps.push(Step::Type(Self::sanitise(&text), None, pen));
+                    }
                     steps.push(Step::Pause);
                     steps.push(Step::Show("\r\n".into()));
                 }
@@ -158,119 +381,425 @@ impl Tale {
         text.replace('\n', "\r\n")
     }
 
-    pub fn tell(&mut self, stdout: &mut StdoutLock) -> Result<(), Box<dyn Error>> {
-        let mut style = Style::default();
+    pub fn title(&self) -> Option<&str> {
+        self.steps.iter().find_map(|step| match step {
+            Step::SetTitle(title) => Some(&title[..]),
+            _ => None,
+        })
+    }
 
-        terminal::enable_raw_mode()?;
+    pub fn tell<W: Write, I: Input>(
+        &mut self,
+        out: &mut W,
+        input: &mut I,
+    ) -> Result<(), Box<dyn Error>> {
+        self.tell_observed(out, input, |_, _| {})
+    }
+
+    /// Tell the lie, calling `on_tag` with the output as it stands whenever a tag is reached.
+    pub fn tell_observed<W: Write, I: Input>(
+        &mut self,
+        out: &mut W,
+        input: &mut I,
+        mut on_tag: impl FnMut(&str, &W),
+    ) -> Result<(), Box<dyn Error>> {
+        input.begin()?;
         execute!(
-            stdout,
+            out,
             Hide,
             DisableBlinking,
             Clear(ClearType::All),
             MoveTo(0, 0)
         )?;
 
-        let mut pc = 0;
-        let mut max_system = 0;
-        let mut system_cache = vec![SystemCacheEntry::default(); self.num_systems];
-        while pc < self.steps.len() {
-            match &self.steps[pc] {
-                Step::Pause => match self.pause(stdout)? {
-                    UnpauseAction::Goto(jmp) => {
-                        if self.num_systems > 0 && jmp > max_system {
-                            self.steps[max_system + 1..=jmp]
-                                .iter()
-                                .filter_map(|step| {
-                                    if let Step::System(system) = step {
-                                        Some(system.capture(&mut system_cache[system.id()]))
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<Result<_, MendaxError>>()?;
+        let mut playback = Playback::new(&self.system_outputs);
+        playback.prefetch = self.start_prefetch(&playback.system_cache, input.terminal_size());
+        'tale: loop {
+            let mut pc = self.start;
+            let mut history = vec![];
+            self.skip(0..pc, &mut playback, out, input)?;
+            while pc < self.end.unwrap_or(self.steps.len()) {
+                self.tags
+                    .iter()
+                    .filter(|(_, tag_pc)| *tag_pc == pc)
+                    .for_each(|(name, _)| on_tag(name, out));
 
-                            max_system = pc;
+                let flow = self.perform(pc, &mut playback, out, input, Pace::Live)?;
+                let redraw = matches!(flow, Flow::Redraw(_));
+                match flow {
+                    Flow::Next => {
+                        if let Step::Pause = self.steps[pc] {
+                            history.push(pc);
                         }
+                        pc += 1;
+                    }
+                    Flow::Goto(jmp) | Flow::Redraw(Some(jmp)) => {
+                        if jmp > pc {
+                            if redraw {
+                                self.rewind(pc, &mut playback, out, input)?;
+                            }
+                            self.skip(pc + 1..jmp, &mut playback, out, input)?;
+                        } else {
+                            self.rewind(jmp, &mut playback, out, input)?;
+                        }
+                        history.push(pc);
                         pc = jmp;
-                        continue;
                     }
-                    UnpauseAction::Exit => break,
-                    UnpauseAction::None => {}
-                },
-                Step::ShowCursor => {
-                    execute!(stdout, Show, DisableBlinking)?;
-                    stdout.flush()?;
-                }
-                Step::HideCursor => {
-                    execute!(stdout, Hide, EnableBlinking)?;
-                    stdout.flush()?;
-                }
-                Step::Ps1 => {
-                    execute!(stdout, Print(style.ps1()))?;
-                    stdout.flush()?;
-                }
-                Step::Type(msg) => style.fake_type(stdout, msg.chars())?,
-                Step::Show(text) => {
-                    execute!(stdout, Print(text))?;
-                    stdout.flush()?;
-                }
-                Step::System(system) => {
-                    if max_system >= pc {
-                        let cache = &system_cache[system.id()];
-                        stdout.write_all(
-                            cache
-                                .output()
-                                .expect("internal error: system command not executed"),
-                        )?;
-                        if cache.requires_newline() {
-                            stdout.write_all(b"\r\n")?;
+                    Flow::Redraw(None) => self.rewind(pc, &mut playback, out, input)?,
+                    Flow::Back => {
+                        if let Some(prev) = history.pop() {
+                            self.rewind(prev, &mut playback, out, input)?;
+                            pc = prev;
                         }
-                        pc += 1;
-                        continue;
                     }
-                    max_system = pc;
+                    Flow::Stop => break,
+                    Flow::Exit => break 'tale,
+                }
+            }
+
+            let Some(wait) = self.loop_wait else {
+                break;
+            };
+            if self.interlude(input, wait)? == UnpauseAction::Exit {
+                break;
+            }
 
-                    let cache = &mut system_cache[system.id()];
-                    system.stream(stdout, cache)?;
-                    if cache.requires_newline() {
-                        execute!(
-                            stdout,
-                            SetAttribute(Attribute::Reverse),
-                            Print("%"),
-                            SetAttribute(Attribute::Reset),
-                            Print("\r\n")
-                        )?;
+            if playback.in_screen {
+                execute!(out, LeaveAlternateScreen, RestorePosition)?;
+            }
+            execute!(out, Hide, Clear(ClearType::All), MoveTo(0, 0))?;
+            playback.reset(self.rerun_systems);
+            if self.rerun_systems {
+                playback.prefetch =
+                    self.start_prefetch(&playback.system_cache, input.terminal_size());
+            }
+        }
+
+        // The tale may end inside a screen when it stops at a tag.
+        if playback.in_screen {
+            execute!(out, LeaveAlternateScreen, RestorePosition)?;
+        }
+        execute!(out, EnableBlinking, Show)?;
+        input.end()?;
+
+        Ok(())
+    }
+
+    fn perform<'tale, W: Write, I: Input>(
+        &'tale self,
+        pc: usize,
+        playback: &mut Playback<'tale>,
+        out: &mut W,
+        input: &mut I,
+        pace: Pace,
+    ) -> Result<Flow, Box<dyn Error>> {
+        let step = &self.steps[pc];
+        if pace == Pace::Quiet && !step.changes_state() {
+            return Ok(Flow::Next);
+        }
+
+        let style = &mut playback.style;
+        match step {
+            Step::Pause => {
+                // When typing is driven by the presenter, the first key pressed starts typing
+                // and only Enter finishes it.
+                let before_typing = matches!(self.steps.get(pc + 1), Some(Step::Type(..)));
+                let after_typing = pc > 0 && matches!(self.steps[pc - 1], Step::Type(..));
+                if pace == Pace::Live && !(self.typer.is_some() && before_typing) {
+                    match self.pause(out, input, self.typer.is_some() && after_typing)? {
+                        UnpauseAction::Goto(jmp) => return Ok(Flow::Goto(jmp)),
+                        UnpauseAction::Redraw(jmp) => return Ok(Flow::Redraw(jmp)),
+                        UnpauseAction::Back => return Ok(Flow::Back),
+                        UnpauseAction::Exit => return Ok(Flow::Exit),
+                        UnpauseAction::None => {}
+                    }
+                }
+            }
+            Step::ShowCursor => {
+                execute!(out, Show, DisableBlinking)?;
+                out.flush()?;
+            }
+            Step::HideCursor => {
+                execute!(out, Hide, EnableBlinking)?;
+                out.flush()?;
+            }
+            Step::Ps1 => {
+                style.draw_prompt(out)?;
+                out.flush()?;
+            }
+            Step::Type(msg, typos, pen) => {
+                let styled = *pen != ContentStyle::new();
+                if styled {
+                    execute!(out, SetStyle(*pen))?;
+                }
+                let flow = match pace {
+                    Pace::Live => match self.typer {
+                        Some(chars_per_key) => self.reveal(out, input, msg, chars_per_key)?,
+                        None => {
+                            style.fake_type(out, input, msg, *typos)?;
+                            Flow::Next
+                        }
+                    },
+                    Pace::Instant | Pace::Quiet => {
+                        execute!(out, Print(msg))?;
+                        Flow::Next
+                    }
+                };
+                if styled {
+                    execute!(out, SetAttribute(Attribute::Reset))?;
+                }
+                return Ok(flow);
+            }
+            Step::Show(text) => {
+                execute!(out, Print(text))?;
+                out.flush()?;
+            }
+            Step::ShowStderr(text) => {
+                let text = text.clone().styled(style.stderr).render();
+                execute!(out, Print(Self::sanitise(&text)))?;
+                out.flush()?;
+            }
+            Step::System(system) => {
+                playback.fetch(system.id());
+                let cache = &mut playback.system_cache[system.id()];
+                if system.interactive() {
+                    if pace == Pace::Live && input.is_interactive() && !self.cached_systems {
+                        execute!(out, Show)?;
+                        system.interact(out, input, cache)?;
+                        execute!(out, Hide)?;
+                    } else if let Some(output) = cache.output() {
+                        out.write_all(output)?;
+                        playback.style.status = cache.status();
+                        return Ok(Flow::Next);
+                    } else {
+                        return Ok(Flow::Next);
                     }
+                } else {
+                    if pace == Pace::Instant && cache.output().is_none() {
+                        system.capture(input.terminal_size(), cache)?;
+                    }
+                    if let Some(output) = cache.output() {
+                        match self.prefetch {
+                            Some(line_delay) if pace == Pace::Live && !line_delay.is_zero() => {
+                                for line in output.split_inclusive(|b| *b == b'\n') {
+                                    out.write_all(line)?;
+                                    out.flush()?;
+                                    input.sleep(line_delay);
+                                }
+                            }
+                            _ => out.write_all(output)?,
+                        }
+                        if cache.requires_newline() {
+                            out.write_all(b"\r\n")?;
+                        }
+                        playback.style.status = cache.status();
+                        return Ok(Flow::Next);
+                    }
+
+                    system.stream(out, input, cache)?;
+                }
+                if cache.requires_newline() {
+                    execute!(
+                        out,
+                        SetAttribute(Attribute::Reverse),
+                        Print("%"),
+                        SetAttribute(Attribute::Reset),
+                        Print("\r\n")
+                    )?;
+                }
 
-                    execute!(stdout, SetAttribute(Attribute::Reset))?;
+                execute!(out, SetAttribute(Attribute::Reset))?;
+                playback.style.status = cache.status();
+            }
+            Step::Sleep(duration) => {
+                if pace == Pace::Live {
+                    input.sleep(*duration);
                 }
-                Step::Sleep(duration) => thread::sleep(*duration),
-                Step::Stop => break,
-                Step::Clear => execute!(stdout, Clear(ClearType::All), MoveTo(0, 0))?,
-                Step::ScreenOpen => {
-                    execute!(stdout, SavePosition, EnterAlternateScreen, MoveTo(0, 0))?
+            }
+            Step::Stop => {
+                if pace == Pace::Live {
+                    return Ok(Flow::Stop);
                 }
-                Step::ScreenClose => execute!(stdout, LeaveAlternateScreen, RestorePosition)?,
+            }
+            Step::Clear => execute!(out, Clear(ClearType::All), MoveTo(0, 0))?,
+            Step::ScreenOpen => {
+                execute!(out, SavePosition, EnterAlternateScreen, MoveTo(0, 0))?;
+                playback.in_screen = true;
+            }
+            Step::ScreenClose => {
+                execute!(out, LeaveAlternateScreen, RestorePosition)?;
+                playback.in_screen = false;
+            }
+
+            Step::SetSpeed(speed) => style.speed = *speed,
+            Step::SetTypos(typos) => style.typos = *typos,
+            Step::SetStatus(status) => style.status = *status,
+            Step::SetTitle(title) => execute!(out, SetTitle(title))?,
+            Step::SetCwd(cwd) => style.cwd = &cwd[..],
+            Step::SetHost(host) => style.host = &host[..],
+            Step::SetUser(user) => style.user = &user[..],
+            Step::SetGitBranch(branch) => style.git_branch = &branch[..],
+            Step::SetGitDirty(dirty) => style.git_dirty = *dirty,
+            Step::SetGitAhead(ahead) => style.git_ahead = *ahead,
+            Step::SetGitBehind(behind) => style.git_behind = *behind,
+            Step::SetPrompt(prompt) => style.prompt = prompt,
+            Step::SetRightPrompt(prompt) => style.right_prompt = Some(prompt),
+            Step::SetStderrStyle(stderr) => style.stderr = *stderr,
+        }
+
+        Ok(Flow::Next)
+    }
 
-                Step::SetSpeed(speed) => style.speed = *speed,
-                Step::SetTitle(title) => execute!(stdout, SetTitle(title))?,
-                Step::SetCwd(cwd) => style.cwd = &cwd[..],
-                Step::SetHost(host) => style.host = &host[..],
-                Step::SetUser(user) => style.user = &user[..],
+    /// Update state as though the given steps had been played, without changing the screen.
+    fn skip<'tale, W: Write, I: Input>(
+        &'tale self,
+        range: Range<usize>,
+        playback: &mut Playback<'tale>,
+        out: &mut W,
+        input: &mut I,
+    ) -> Result<(), Box<dyn Error>> {
+        for step in &self.steps[..range.end] {
+            if let Step::System(system) = step {
+                playback.fetch(system.id());
+                let cache = &mut playback.system_cache[system.id()];
+                if !system.interactive() && cache.output().is_none() {
+                    system.capture(input.terminal_size(), cache)?;
+                }
             }
-            pc += 1;
         }
 
-        execute!(stdout, EnableBlinking, Show)?;
-        terminal::disable_raw_mode()?;
+        for pc in range {
+            self.perform(pc, playback, out, input, Pace::Quiet)?;
+        }
+
+        Ok(())
+    }
+
+    /// Start running the system commands without output in `cache` in the background, if
+    /// prefetching. As later commands may depend on what the presenter does in interactive ones,
+    /// only those before the first interactive command are run.
+    fn start_prefetch(
+        &self,
+        cache: &[SystemCacheEntry],
+        size: Option<(u16, u16)>,
+    ) -> Option<Prefetch> {
+        self.prefetch?;
+        Some(Prefetch::start(
+            self.systems()
+                .into_iter()
+                .take_while(|system| !system.interactive())
+                .filter(|system| cache[system.id()].output().is_none())
+                .cloned()
+                .collect(),
+            size,
+        ))
+    }
+
+    /// Redraw the screen as it would be had the tale been played up to `pc`.
+    fn rewind<'tale, W: Write, I: Input>(
+        &'tale self,
+        pc: usize,
+        playback: &mut Playback<'tale>,
+        out: &mut W,
+        input: &mut I,
+    ) -> Result<(), Box<dyn Error>> {
+        if playback.in_screen {
+            execute!(out, LeaveAlternateScreen, RestorePosition)?;
+        }
+        execute!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        playback.reset(false);
+
+        // What came before the tale began was never on screen.
+        let start = self.start.min(pc);
+        self.skip(0..start, playback, out, input)?;
+        for pc in start..pc {
+            self.perform(pc, playback, out, input, Pace::Instant)?;
+        }
+        out.flush()?;
 
         Ok(())
     }
 
-    fn pause(&self, stdout: &mut StdoutLock) -> Result<UnpauseAction, Box<dyn Error>> {
+    /// Wait between repetitions of a looping tale, giving the presenter a chance to exit.
+    fn interlude<I: Input>(
+        &self,
+        input: &mut I,
+        wait: Duration,
+    ) -> Result<UnpauseAction, Box<dyn Error>> {
+        if !input.poll(wait)? {
+            return Ok(UnpauseAction::None);
+        }
+
+        match input.read()? {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => Err(Box::new(MendaxError::KeyboardInterrupt)),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('!'),
+                ..
+            })
+            | Event::Key(KeyEvent {
+                code: KeyCode::Char('d'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => Ok(UnpauseAction::Exit),
+            _ => Ok(UnpauseAction::None),
+        }
+    }
+
+    /// Reveal `msg` as the presenter presses keys, `chars_per_key` characters at a time.
+    fn reveal<W: Write, I: Input>(
+        &self,
+        out: &mut W,
+        input: &mut I,
+        msg: &str,
+        chars_per_key: usize,
+    ) -> Result<Flow, Box<dyn Error>> {
+        let mut chars = msg.chars().peekable();
+        while chars.peek().is_some() {
+            match input.read()? {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                }) => return Err(Box::new(MendaxError::KeyboardInterrupt)),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('d'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                }) => return Ok(Flow::Exit),
+                Event::Key(_) => {
+                    let chunk: String = chars.by_ref().take(chars_per_key).collect();
+                    execute!(out, Print(chunk))?;
+                    out.flush()?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    /// Wait for the presenter to continue. If `await_enter`, keys which would otherwise just
+    /// continue are ignored so that overshooting the end of a typed command does not run it.
+    fn pause<W: Write, I: Input>(
+        &self,
+        out: &mut W,
+        input: &mut I,
+        await_enter: bool,
+    ) -> Result<UnpauseAction, Box<dyn Error>> {
         let mut printed = false;
+        let mut held = false;
         loop {
-            match event::read()? {
+            if let Some(delay) = self.autoplay {
+                if !held && !input.poll(delay)? {
+                    return Ok(UnpauseAction::None);
+                }
+            }
+
+            match input.read()? {
                 Event::Key(KeyEvent {
                     code: KeyCode::Char('c'),
                     modifiers: KeyModifiers::CONTROL,
@@ -281,13 +810,18 @@ impl Tale {
                     ..
                 }) => {
                     if !printed {
-                        write!(stdout, "\r\n")?;
+                        write!(out, "\r\n")?;
                         printed = true;
                     }
                     write!(
-                        stdout,
-                        "mendax: press '/' to jump to a tag, '!' to exit, 'h' or '?' to show this help\r\n"
-                        )?;
+                        out,
+                        "mendax: press '/' to jump to a tag, 'b' to go back, '!' to exit, 'h' or '?' to show this help{}\r\n",
+                        if self.autoplay.is_some() {
+                            ", any other key to hold or resume"
+                        } else {
+                            ""
+                        }
+                    )?;
                 }
                 Event::Key(KeyEvent {
                     code: KeyCode::Char('!'),
@@ -298,53 +832,34 @@ impl Tale {
                     modifiers: KeyModifiers::CONTROL,
                     ..
                 }) => return Ok(UnpauseAction::Exit),
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('b') | KeyCode::Left,
+                    ..
+                }) => return Ok(UnpauseAction::Back),
                 Event::Key(KeyEvent {
                     code: KeyCode::Char('/'),
                     ..
                 }) => {
-                    terminal::disable_raw_mode()?;
-                    let mut incorrect = false;
-                    let mut tag = String::new();
-                    let pc = loop {
-                        if !printed {
-                            writeln!(stdout)?;
-                            printed = true;
-                        }
-                        if !incorrect {
-                            write!(stdout, "mendax: enter tag: ")?;
-                        } else {
-                            write!(
-                                stdout,
-                                "mendax: tag incorrect; enter tag or '?' to list available: "
-                            )?;
-                        }
-                        stdout.flush()?;
-
-                        tag.clear();
-                        io::stdin().read_line(&mut tag)?;
-                        let tag = tag.trim();
-
-                        if tag.is_empty() {
-                            println!("mendax: jump cancelled");
-                            return Ok(UnpauseAction::None);
-                        }
-                        if tag == "?" {
-                            let mut known_tags =
-                                self.tags.keys().map(|k| k.as_str()).collect::<Vec<_>>();
-                            known_tags.sort();
-
-                            println!("{}", known_tags.join(", "));
-                            continue;
-                        }
-                        if let Some(pc) = self.tags.get(tag) {
-                            break *pc;
-                        }
-                        incorrect = true;
-                    };
-                    terminal::enable_raw_mode()?;
-                    return Ok(UnpauseAction::Goto(pc));
+                    let fits = picker::fits(input);
+                    let picked = picker::pick(&self.tags, out, input)?;
+                    if !fits {
+                        // Making room for the picker scrolled the screen.
+                        return Ok(UnpauseAction::Redraw(picked));
+                    }
+                    if let Some(pc) = picked {
+                        return Ok(UnpauseAction::Goto(pc));
+                    }
+                }
+                Event::Key(KeyEvent { code, .. }) => {
+                    if await_enter && code != KeyCode::Enter {
+                        continue;
+                    }
+                    if self.autoplay.is_some() && !held {
+                        held = true;
+                        continue;
+                    }
+                    return Ok(UnpauseAction::None);
                 }
-                Event::Key(KeyEvent { .. }) => return Ok(UnpauseAction::None),
                 _ => {}
             }
         }
@@ -356,10 +871,13 @@ enum Step {
     Pause,
     Ps1,
     Show(String),
+    /// Show some text printed to stderr, in the stderr style.
+    ShowStderr(Text),
     System(System),
     Sleep(Duration),
     Stop,
-    Type(String),
+    /// Type some text in the given style, with an optional chance of mistyping each character.
+    Type(String, Option<f64>, ContentStyle),
     Clear,
     ScreenOpen,
     ScreenClose,
@@ -368,27 +886,66 @@ enum Step {
     HideCursor,
 
     SetSpeed(f64),
+    SetTypos(f64),
+    SetStatus(u8),
     SetTitle(String),
     SetCwd(String),
     SetHost(String),
     SetUser(String),
+    SetGitBranch(String),
+    SetGitDirty(bool),
+    SetGitAhead(u32),
+    SetGitBehind(u32),
+    SetPrompt(Prompt),
+    SetRightPrompt(Prompt),
+    SetStderrStyle(ContentStyle),
 }
 
+impl Step {
+    fn changes_state(&self) -> bool {
+        matches!(
+            self,
+            Self::Clear
+                | Self::ScreenOpen
+                | Self::ScreenClose
+                | Self::SetSpeed(_)
+                | Self::SetTypos(_)
+                | Self::SetStatus(_)
+                | Self::SetTitle(_)
+                | Self::SetCwd(_)
+                | Self::SetHost(_)
+                | Self::SetUser(_)
+                | Self::SetGitBranch(_)
+                | Self::SetGitDirty(_)
+                | Self::SetGitAhead(_)
+                | Self::SetGitBehind(_)
+                | Self::SetPrompt(_)
+                | Self::SetRightPrompt(_)
+                | Self::SetStderrStyle(_)
+        )
+    }
+}
+
+#[derive(PartialEq)]
 enum UnpauseAction {
     Goto(usize),
+    /// Draw the screen afresh, then go to the given step if there is one.
+    Redraw(Option<usize>),
+    Back,
     Exit,
     None,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct System {
     cmd: String,
     id: usize,
+    options: SystemOptions,
 }
 
 impl System {
-    pub fn new(cmd: String, id: usize) -> Self {
-        Self { cmd, id }
+    pub fn new(cmd: String, id: usize, options: SystemOptions) -> Self {
+        Self { cmd, id, options }
     }
 
     fn cmd(&self) -> &str {
@@ -399,98 +956,465 @@ impl System {
         self.id
     }
 
-    pub fn capture(&self, cache: &mut SystemCacheEntry) -> Result<(), MendaxError> {
-        self.exec(None, cache)
+    fn interactive(&self) -> bool {
+        self.options.interactive
+    }
+
+    pub fn capture(
+        &self,
+        size: Option<(u16, u16)>,
+        cache: &mut SystemCacheEntry,
+    ) -> Result<(), MendaxError> {
+        self.exec(None, None, false, size, cache)
     }
 
-    pub fn stream(
+    /// Stream the command's output, stopping the command if the presenter presses Ctrl-C.
+    pub fn stream<W: Write, I: Input>(
         &self,
-        out: &mut StdoutLock,
+        out: &mut W,
+        input: &mut I,
         cache: &mut SystemCacheEntry,
     ) -> Result<(), MendaxError> {
-        self.exec(Some(out), cache)
+        let size = input.terminal_size();
+        self.exec(Some(out), Some(input), false, size, cache)
+    }
+
+    /// Stream the command's output while forwarding the presenter's keystrokes to it.
+    pub fn interact<W: Write, I: Input>(
+        &self,
+        out: &mut W,
+        input: &mut I,
+        cache: &mut SystemCacheEntry,
+    ) -> Result<(), MendaxError> {
+        let size = input.terminal_size();
+        self.exec(Some(out), Some(input), true, size, cache)
     }
 
     fn exec(
         &self,
-        mut out: Option<&mut StdoutLock>,
+        out: Option<&mut dyn Write>,
+        input: Option<&mut dyn Input>,
+        forward_keys: bool,
+        size: Option<(u16, u16)>,
         cache: &mut SystemCacheEntry,
     ) -> Result<(), MendaxError> {
+        self.exec_in_pty(out, input, forward_keys, size, cache)
+            .map_err(|error| MendaxError::System {
+                cmd: self.cmd().into(),
+                error,
+            })
+    }
+
+    /// Run the command in a pseudo-terminal of the given `size`, or that of the current one, so
+    /// that it behaves as it would if the presenter had typed it. Unless `forward_keys` is set,
+    /// `input` is only watched for Ctrl-C.
+    fn exec_in_pty(
+        &self,
+        mut out: Option<&mut dyn Write>,
+        mut input: Option<&mut dyn Input>,
+        forward_keys: bool,
+        size: Option<(u16, u16)>,
+        cache: &mut SystemCacheEntry,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (cols, rows) = size.or_else(|| terminal::size().ok()).unwrap_or((80, 24));
+        let pty = native_pty_system().openpty(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })?;
+
+        let mut cmd = CommandBuilder::new("/bin/sh");
+        cmd.args(["-c", self.cmd()]);
+        if self.options.clear_env {
+            cmd.env_clear();
+            if let Some(term) = env::var_os("TERM") {
+                cmd.env("TERM", term);
+            }
+        }
+        if !self.interactive() {
+            // Nobody could page through the output, so pagers would wait forever.
+            for key in ["PAGER", "GIT_PAGER", "MANPAGER"] {
+                cmd.env(key, "cat");
+            }
+        }
+        for (key, value) in &self.options.env {
+            cmd.env(key, value);
+        }
+        let cwd = env::current_dir()?;
+        cmd.cwd(match &self.options.cwd {
+            Some(dir) => cwd.join(dir),
+            None => cwd,
+        });
+        let mut child = pty.slave.spawn_command(cmd)?;
+        drop(pty.slave);
+
+        // Output is read on another thread so that keystrokes can be forwarded while waiting.
+        let mut reader = pty.master.try_clone_reader()?;
+        let (chunks, received) = mpsc::channel();
+        thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(len) => {
+                        if chunks.send(chunk[..len].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    // Once the command exits, reading fails rather than reaching the end on
+                    // some platforms.
+                    Err(_) => break,
+                }
+            }
+        });
+
+        // Output is held back if it might be replaced by the fallback, unless the presenter needs
+        // to see it to interact.
+        let held = self.options.fallback.is_some() && !forward_keys;
+        let deadline = self.options.timeout.map(|timeout| Instant::now() + timeout);
+        let mut writer = pty.master.take_writer()?;
         let mut buf = Vec::new();
-        let stream = Exec::shell(self.cmd())
-            .stderr(Redirection::Merge)
-            .stream_stdout()?;
-        let mut final_newline = false;
-        let mut stdout_nonempty = false;
-        for b in stream.bytes() {
-            stdout_nonempty = true;
-            let b = b.map_err(PopenError::from)?;
-            final_newline = b == b'\n';
-            if final_newline {
+        let mut killed_by = None;
+        loop {
+            let wait = match (&input, deadline) {
+                (Some(_), _) => Some(Duration::from_millis(10)),
+                (None, Some(deadline)) => Some(deadline.saturating_duration_since(Instant::now())),
+                (None, None) => None,
+            };
+            let chunk = match wait {
+                Some(wait) => received.recv_timeout(wait),
+                None => received.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        killed_by = Some(libc::SIGKILL);
+                        break;
+                    }
+                    if let Some(input) = &mut input {
+                        if forward_keys {
+                            while input.poll(Duration::ZERO)? {
+                                match input.read()? {
+                                    Event::Key(key) => {
+                                        if let Some(bytes) = input::key_bytes(&key) {
+                                            writer.write_all(&bytes)?;
+                                        }
+                                    }
+                                    Event::Paste(text) => writer.write_all(text.as_bytes())?,
+                                    Event::Resize(cols, rows) => pty.master.resize(PtySize {
+                                        rows,
+                                        cols,
+                                        pixel_width: 0,
+                                        pixel_height: 0,
+                                    })?,
+                                    _ => {}
+                                }
+                            }
+                            writer.flush()?;
+                        } else if input.interrupted()? {
+                            killed_by = Some(libc::SIGINT);
+                            break;
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            if !held {
+                if let Some(out) = &mut out {
+                    out.write_all(&chunk)?;
+                    out.flush()?;
+                }
+            }
+            buf.extend_from_slice(&chunk);
+        }
+
+        if killed_by.is_some() {
+            // The command is its own process group leader, so this also stops anything it
+            // started. Pagers and the like ignore SIGINT, so even Ctrl-C kills outright.
+            if let Some(pid) = child.process_id() {
+                // SAFETY: `killpg` only sends a signal and touches no memory.
+                unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+            }
+        }
+        let status = child.wait()?;
+        cache.status = match killed_by {
+            // As a shell would report it.
+            Some(signal) => 128 + signal as u8,
+            None => status.exit_code().try_into().unwrap_or(u8::MAX),
+        };
+
+        let failed =
+            killed_by.is_some() || status.exit_code() != self.options.expected_status.unwrap_or(0);
+        match &self.options.fallback {
+            Some(fallback) if failed => {
+                // The fallback stands in for the command having worked.
+                cache.status = 0;
+                buf = fallback.concat().into_bytes();
                 if let Some(out) = &mut out {
-                    out.write_all(b"\r\n").map_err(PopenError::from)?;
+                    out.write_all(&buf)?;
                 }
-                buf.write_all(b"\r\n").map_err(PopenError::from)?;
-            } else {
+            }
+            _ if held => {
                 if let Some(out) = &mut out {
-                    out.write_all(&[b]).map_err(PopenError::from)?;
+                    out.write_all(&buf)?;
                 }
-                buf.write_all(&[b]).map_err(PopenError::from)?;
             }
+            _ => {}
         }
 
+        cache.requires_newline = !buf.is_empty() && !buf.ends_with(b"\n");
         cache.output = Some(buf);
-        cache.requires_newline = stdout_nonempty && !final_newline;
 
         Ok(())
     }
 }
 
+/// How the steps of a tale are performed.
+#[derive(Clone, Copy, PartialEq)]
+enum Pace {
+    /// As the audience should see them.
+    Live,
+    /// Without waiting, as when redrawing the screen.
+    Instant,
+    /// Only updating state, as when skipping ahead.
+    Quiet,
+}
+
+/// What to do after performing a step.
+enum Flow {
+    Next,
+    Goto(usize),
+    /// Draw the screen afresh, then go to the given step or stay at this one.
+    Redraw(Option<usize>),
+    Back,
+    Stop,
+    Exit,
+}
+
+type PrefetchResult = Result<SystemCacheEntry, Box<dyn Error + Send + Sync>>;
+
+/// System command output being collected in the background, in order.
+struct Prefetch {
+    received: mpsc::Receiver<(usize, PrefetchResult)>,
+    pending: Vec<usize>,
+}
+
+impl Prefetch {
+    fn start(systems: Vec<System>, size: Option<(u16, u16)>) -> Self {
+        let pending = systems.iter().map(System::id).collect();
+        let (results, received) = mpsc::channel();
+        thread::spawn(move || {
+            for system in systems {
+                let mut entry = SystemCacheEntry::default();
+                let result = system.exec_in_pty(None, None, false, size, &mut entry);
+                if results.send((system.id(), result.map(|()| entry))).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { received, pending }
+    }
+
+    /// Wait for the `id`th system command to finish if it is being prefetched, storing the
+    /// output of each command which finishes in `cache`. Commands which fail are left for the
+    /// tale to run and report.
+    fn wait(&mut self, id: usize, cache: &mut [SystemCacheEntry]) {
+        while self.pending.contains(&id) {
+            let Ok((done, result)) = self.received.recv() else {
+                self.pending.clear();
+                break;
+            };
+            self.pending.retain(|pending| *pending != done);
+            if let Ok(entry) = result {
+                cache[done] = entry;
+            }
+        }
+    }
+}
+
+/// The state of a tale as it is being told.
+struct Playback<'tale> {
+    style: Style<'tale>,
+    in_screen: bool,
+    system_outputs: &'tale [SystemCacheEntry],
+    system_cache: Vec<SystemCacheEntry>,
+    prefetch: Option<Prefetch>,
+}
+
+impl<'tale> Playback<'tale> {
+    fn new(system_outputs: &'tale [SystemCacheEntry]) -> Self {
+        Self {
+            style: Style::default(),
+            in_screen: false,
+            system_outputs,
+            system_cache: system_outputs.to_vec(),
+            prefetch: None,
+        }
+    }
+
+    /// Wait for the output of the `id`th system command, if it is being prefetched.
+    fn fetch(&mut self, id: usize) {
+        if let Some(prefetch) = &mut self.prefetch {
+            prefetch.wait(id, &mut self.system_cache);
+        }
+    }
+
+    fn reset(&mut self, clear_system_cache: bool) {
+        self.style = Style::default();
+        self.in_screen = false;
+        if clear_system_cache {
+            self.system_cache.clone_from_slice(self.system_outputs);
+        }
+    }
+}
+
 pub struct Style<'lie> {
+    prompt: &'lie Prompt,
+    right_prompt: Option<&'lie Prompt>,
     speed: f64,
+    typos: f64,
     cwd: &'lie str,
     host: &'lie str,
     user: &'lie str,
+    git_branch: &'lie str,
+    git_dirty: bool,
+    git_ahead: u32,
+    git_behind: u32,
+    status: u8,
+    stderr: ContentStyle,
 }
 
 impl<'lie> Style<'lie> {
-    fn ps1(&self) -> String {
-        format!(
-            "{}{}{}:{}$ ",
-            self.user.bold().green(),
-            "@".bold().green(),
-            self.host.bold().green(),
-            self.cwd.blue().bold()
-        )
+    /// Draw the prompt, with the right prompt against the right edge of its last line.
+    fn draw_prompt(&self, out: &mut impl Write) -> io::Result<()> {
+        let failed = self.status != 0;
+        let ps1 = self
+            .prompt
+            .render(failed, |placeholder| self.fill(placeholder));
+        let last_line = match ps1.rsplit_once('\n') {
+            Some((above, last_line)) => {
+                queue!(out, Print(above.replace('\n', "\r\n")), Print("\r\n"))?;
+                last_line
+            }
+            None => &ps1,
+        };
+
+        if let Some(right_prompt) = self.right_prompt {
+            let width = right_prompt.width(|placeholder| self.fill(placeholder));
+            if width > 0 {
+                // Moving right stops at the edge of the terminal, whatever its width.
+                queue!(out, Print("\r"), MoveRight(u16::MAX))?;
+                if width > 1 {
+                    queue!(out, MoveLeft(width as u16 - 1))?;
+                }
+                queue!(
+                    out,
+                    Print(right_prompt.render(failed, |placeholder| self.fill(placeholder))),
+                    Print("\r")
+                )?;
+            }
+        }
+
+        queue!(out, Print(last_line))
     }
 
-    fn fake_type<T: Display>(
+    fn fill(&self, placeholder: Placeholder) -> String {
+        match placeholder {
+            Placeholder::User => self.user.into(),
+            Placeholder::Host => self.host.into(),
+            Placeholder::Cwd => self.cwd.into(),
+            Placeholder::Git => {
+                if self.git_branch.is_empty() {
+                    return String::new();
+                }
+                let mut git = format!(" ({}", self.git_branch);
+                if self.git_dirty {
+                    git.push('*');
+                }
+                if self.git_ahead > 0 {
+                    git.push_str(&format!(" \u{2191}{}", self.git_ahead));
+                }
+                if self.git_behind > 0 {
+                    git.push_str(&format!(" \u{2193}{}", self.git_behind));
+                }
+                git.push(')');
+                git
+            }
+            Placeholder::GitBranch => self.git_branch.into(),
+            Placeholder::Exit => match self.status {
+                0 => String::new(),
+                status => format!(" [{status}]"),
+            },
+            Placeholder::Time => prompt::time(),
+        }
+    }
+
+    /// Type `text`, occasionally hitting a neighbouring key by mistake then correcting it. The
+    /// chance of each mistake is `typos` if given, otherwise that of the current look.
+    fn fake_type(
         &self,
-        stdout: &mut StdoutLock,
-        ts: impl Iterator<Item = T>,
+        out: &mut impl Write,
+        input: &mut impl Input,
+        text: &str,
+        typos: Option<f64>,
     ) -> Result<(), Box<dyn Error>> {
+        let typos = typos.unwrap_or(self.typos);
         let mut rng = rand::thread_rng();
+        for c in text.chars() {
+            if typos > 0.0 && rng.gen_bool(typos) {
+                if let Some(miss) = typos::near_miss(c, &mut rng) {
+                    self.keystroke(out, input, miss)?;
+                    for _ in 0..rng.gen_range(2..5) {
+                        if let Some(interval) = self.keystroke_interval() {
+                            input.sleep(interval);
+                        }
+                    }
+                    self.keystroke(out, input, "\x08 \x08")?;
+                }
+            }
 
-        for t in ts {
-            if self.speed != 0.0 {
-                let deviation = self.speed * 0.5;
-                let interval = rng.gen_range(self.speed - deviation..self.speed + deviation);
+            self.keystroke(out, input, c)?;
+        }
 
-                thread::sleep(Duration::from_millis((interval * 1000.0) as u64));
-            }
+        Ok(())
+    }
 
-            execute!(stdout, Print(t))?;
-            stdout.flush()?;
+    fn keystroke(
+        &self,
+        out: &mut impl Write,
+        input: &mut impl Input,
+        key: impl Display,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(interval) = self.keystroke_interval() {
+            input.sleep(interval);
         }
 
+        execute!(out, Print(key))?;
+        out.flush()?;
         Ok(())
     }
+
+    fn keystroke_interval(&self) -> Option<Duration> {
+        if self.speed == 0.0 {
+            return None;
+        }
+
+        let deviation = self.speed * 0.5;
+        let interval = rand::thread_rng().gen_range(self.speed - deviation..self.speed + deviation);
+        Some(Duration::from_millis((interval * 1000.0) as u64))
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 struct SystemCacheEntry {
     output: Option<Vec<u8>>,
     requires_newline: bool,
+    status: u8,
 }
 
 impl SystemCacheEntry {
@@ -501,15 +1425,776 @@ impl SystemCacheEntry {
     fn requires_newline(&self) -> bool {
         self.requires_newline
     }
+
+    fn status(&self) -> u8 {
+        self.status
+    }
 }
 
 impl Default for Style<'_> {
     fn default() -> Self {
         Self {
-            speed: 0.040,
+            prompt: &DEFAULT_PROMPT,
+            right_prompt: None,
+            speed: DEFAULT_SPEED,
+            typos: 0.0,
             cwd: "~",
             host: "ubuntu",
             user: "ubuntu",
+            git_branch: "",
+            git_dirty: false,
+            git_ahead: 0,
+            git_behind: 0,
+            status: 0,
+            stderr: ContentStyle::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        input::{test::Scripted, Headless},
+        lie,
+    };
+    use crossterm::style::{Color, SetForegroundColor, Stylize};
+    use std::error::Error;
+
+    fn tell(script: &str, input: &mut Scripted) -> Result<String, Box<dyn Error>> {
+        let lie = lie::test::test_script(false, script)?;
+        let mut out = vec![];
+        Tale::from(lie).tell(&mut out, input)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn tell_to_buffer() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.run("echo foo", "foo\n");
+                lie.show("bar\n");
+            "#,
+            &mut Scripted::keys("   "),
+        )?;
+
+        assert!(out.contains("echo foo"), "{out:?}");
+        assert!(out.contains("foo\r\n"), "{out:?}");
+        assert!(out.contains("bar\r\n"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn typos() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0, typos: 1.0 });
+                lie.run("echo foo");
+                lie.run("echo bar", #{ typos: 0.0 });
+            "#,
+            &mut Scripted::keys("     "),
+        )?;
+
+        assert!(!out.contains("echo foo"), "{out:?}");
+        assert!(out.contains("echo bar"), "{out:?}");
+
+        let mut term = vt100::Parser::new(24, 80, 0);
+        term.process(out.as_bytes());
+        let screen = term.screen().contents();
+        assert!(screen.contains("$ echo foo\n"), "{screen}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn typer() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            false,
+            r#"
+                lie.run("echo foo", "foo\n");
+                lie.tag("ls");
+                lie.run("ls");
+            "#,
+        )?;
+
+        let mut out = vec![];
+        Tale::from(lie.clone())
+            .with_typer(Some(3))
+            .tell(&mut out, &mut Scripted::keys("abcde\nfg\n "))?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("echo foo\r\n"), "{out:?}");
+        assert!(out.contains("\x1b[?12hfoo\r\n"), "{out:?}");
+        assert!(out.contains("ls\r\n"), "{out:?}");
+
+        for keys in [
+            Scripted::keys("abcde "),
+            Scripted::keys("abc")
+                .then(KeyCode::Tab)
+                .then(KeyCode::Backspace)
+                .then(KeyCode::Esc)
+                .then(KeyCode::Up),
+        ] {
+            let mut out = vec![];
+            assert!(Tale::from(lie.clone())
+                .with_typer(Some(3))
+                .tell(&mut out, &mut { keys })
+                .is_err());
+            let out = String::from_utf8(out)?;
+            assert!(out.contains("echo foo"), "{out:?}");
+            assert!(!out.contains("foo\r\n"), "{out:?}");
+        }
+
+        // mendax's own keys still work once the command is typed.
+        let mut out = vec![];
+        Tale::from(lie.clone())
+            .with_typer(Some(3))
+            .tell(&mut out, &mut Scripted::keys("abch/ls\nf\n "))?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("mendax: press"), "{out:?}");
+        assert!(!out.contains("\x1b[?12hfoo\r\n"), "{out:?}");
+        assert!(out.contains("ls\r\n"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn prompts() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0, prompt: "{cwd}\n> ", right_prompt: "[dim]{user}[/]" });
+                lie.run("ls");
+                lie.clear();
+                lie.run("pwd");
+                lie.screen(|lie| {
+                    lie.run("top");
+                });
+            "#,
+            &mut Scripted::keys("          "),
+        )?;
+
+        let mut term = vt100::Parser::new(6, 20, 0);
+        term.process(out.as_bytes());
+        let rows: Vec<_> = term.screen().rows(0, 20).collect();
+        assert_eq!(
+            rows[..4],
+            ["~", "> pwd         ubuntu", "~", ">             ubuntu"]
+        );
+
+        let mut screen = vt100::Parser::new(6, 20, 0);
+        let (_, in_screen) = out.split_once("\x1b[?1049h").unwrap();
+        screen.process(in_screen.as_bytes());
+        let rows: Vec<_> = screen.screen().rows(0, 20).collect();
+        assert_eq!(rows[..2], ["~", "> top         ubuntu"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn git_prompt() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0, prompt: "{cwd}{git}$ " });
+                lie.run("ls");
+                lie.git(#{ branch: "main", dirty: true, ahead: 2, follow_checkouts: true });
+                lie.run("git checkout -b foo");
+                lie.git(#{ branch: "" });
+            "#,
+            &mut Scripted::keys("        "),
+        )?;
+
+        let mut term = vt100::Parser::new(6, 40, 0);
+        term.process(out.as_bytes());
+        let rows: Vec<_> = term.screen().rows(0, 40).collect();
+        assert_eq!(
+            rows[..3],
+            ["~$ ls", "~ (main* \u{2191}2)$ git checkout -b foo", "~$ "]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn stderr() -> Result<(), Box<dyn Error>> {
+        let script = r#"
+            lie.look(#{ speed: 0.0, final_prompt: false });
+            lie.run("make", ["ok\n", #{ stderr: "oops\n" }]);
+            lie.look(#{ stderr: "red" });
+            lie.run("make", ["ok\n", #{ stderr: "oops\n" }]);
+        "#;
+        let out = tell(script, &mut Scripted::keys("        "))?;
+        assert!(out.contains("ok\r\noops\r\n"));
+        assert!(out.contains(&format!("ok\r\n{}", "oops\r\n".red())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn styled_text() -> Result<(), Box<dyn Error>> {
+        let script = r#"
+            lie.look(#{ speed: 0.0, final_prompt: false, stderr: "dim" });
+            lie.show(markup("[green]ok[/]\n"));
+            lie.run("make", [style("done").bold(), #{ stderr: style("oops").red() }]);
+            lie.enter("a" + style("b").blue());
+        "#;
+        let out = tell(script, &mut Scripted::keys("        "))?;
+        assert!(out.contains(&format!("{}\r\n", "ok".green())));
+        assert!(out.contains(&format!("{}{}", "done".bold(), "oops".red().dim())));
+        assert!(out.contains(&format!(
+            "a{}b{}",
+            SetForegroundColor(Color::Blue),
+            SetAttribute(Attribute::Reset)
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn exit_status() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0, prompt: "{cwd}{exit}$ " });
+                lie.run("make", "error\n", #{ status: 2 });
+                lie.run("echo $?", lie.status.to_string() + "\n");
+            "#,
+            &mut Scripted::keys("        "),
+        )?;
+
+        let mut term = vt100::Parser::new(6, 40, 0);
+        term.process(out.as_bytes());
+        let rows: Vec<_> = term.screen().rows(0, 40).collect();
+        assert_eq!(
+            rows[..5],
+            ["~$ make", "error", "~ [2]$ echo $?", "2", "~$ "]
+        );
+
+        let lie = lie::test::test_script(
+            true,
+            r#"
+                lie.look(#{ speed: 0.0, prompt: "{exit}$ " });
+                lie.run("make", "", #{ status: 2 });
+                lie.system("false");
+                lie.system("true");
+                lie.screen(|lie| lie.run("make", "", #{ status: 3 }));
+            "#,
+        )?;
+        let mut out = vec![];
+        Tale::from(lie).tell(&mut out, &mut Scripted::keys(&" ".repeat(12)))?;
+        let mut term = vt100::Parser::new(6, 40, 0);
+        term.process(&out);
+        let rows: Vec<_> = term.screen().rows(0, 40).collect();
+        assert_eq!(rows[..4], ["$ make", " [2]$ false", " [1]$ true", " [3]$ "]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn exit() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.run("echo foo", "foo\n");
+                lie.run("echo bar", "bar\n");
+            "#,
+            &mut Scripted::keys("  !"),
+        )?;
+
+        assert!(out.contains("echo foo"), "{out:?}");
+        assert!(!out.contains("echo bar"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn jump() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.run("echo foo", "foo\n");
+                lie.run("echo bar", "bar\n");
+                lie.tag("baz");
+                lie.run("echo baz", "baz\n");
+            "#,
+            &mut Scripted::keys("/baz\n   "),
+        )?;
+
+        assert!(!out.contains("echo foo"), "{out:?}");
+        assert!(!out.contains("echo bar"), "{out:?}");
+        assert!(out.contains("echo baz"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn autoplay() -> Result<(), Box<dyn Error>> {
+        let script = r#"
+            lie.look(#{ speed: 0.0 });
+            lie.run("echo foo", "foo\n");
+            lie.run("echo bar", "bar\n");
+        "#;
+
+        let mut out = vec![];
+        Tale::from(lie::test::test_script(false, script)?)
+            .with_autoplay(Some(Duration::from_secs(1)))
+            .tell(&mut out, &mut Scripted::default())?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("echo bar"), "{out:?}");
+
+        let mut out = vec![];
+        Tale::from(lie::test::test_script(false, script)?)
+            .with_autoplay(Some(Duration::from_secs(1)))
+            .tell(&mut out, &mut Scripted::keys("x!"))?;
+        let out = String::from_utf8(out)?;
+        assert!(!out.contains("echo foo"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn looping() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            false,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.run("echo foo", "foo\n");
+                lie.stop();
+                lie.run("echo bar", "bar\n");
+            "#,
+        )?;
+
+        let mut out = vec![];
+        Tale::from(lie)
+            .with_loop(Some(Duration::ZERO), false)
+            .tell(&mut out, &mut Scripted::keys("  x  !"))?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(out.matches("echo foo").count(), 2, "{out:?}");
+        assert!(!out.contains("echo bar"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn back() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.run("echo foo", "foo\n");
+                lie.screen("man bar", |lie| {
+                    lie.show("BAR(1)\n");
+                });
+            "#,
+            &mut Scripted::keys("   b    "),
+        )?;
+
+        assert_eq!(out.matches("echo foo").count(), 2, "{out:?}");
+        assert_eq!(out.matches("man bar").count(), 2, "{out:?}");
+        assert_eq!(out.matches("BAR(1)").count(), 1, "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn picker_leaves_no_trace() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            false,
+            r#"
+                lie.look(#{ speed: 0.0, prompt: "$ " });
+                for i in 1..8 {
+                    lie.show(`${i}` + "\n");
+                }
+                lie.tag("end");
+                lie.run("ls");
+            "#,
+        )?;
+
+        for keys in ["/\x1b", "/end\n"] {
+            let mut term = vt100::Parser::new(8, 20, 0);
+            Tale::from(lie.clone()).tell(
+                &mut term,
+                &mut Scripted::keys(keys)
+                    .on_screen((2, 7), (20, 8))
+                    .then_keys("!"),
+            )?;
+            assert_eq!(
+                term.screen().contents(),
+                "1\n2\n3\n4\n5\n6\n7\n$ ",
+                "{keys:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn jump_restores_state() -> Result<(), Box<dyn Error>> {
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.tag("start");
+                lie.screen(|lie| {
+                    lie.cd("/tmp");
+                    lie.look(#{ title: "temporary" });
+                });
+                lie.tag("end");
+                lie.run("echo foo");
+            "#,
+            &mut Scripted::keys("/end\n   ").on_screen((0, 0), (80, 24)),
+        )?;
+
+        assert!(!out.contains("cd /tmp"), "{out:?}");
+        assert!(out.contains("/tmp\x1b[0m$ "), "{out:?}");
+        assert!(out.contains("temporary"), "{out:?}");
+        assert_eq!(out.matches("\x1b[?1049h").count(), 1, "{out:?}");
+        assert_eq!(out.matches("\x1b[?1049l").count(), 1, "{out:?}");
+
+        let out = tell(
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.tag("start");
+                lie.run("echo foo");
+                lie.screen("man foo", |lie| {
+                    lie.pause();
+                });
+            "#,
+            &mut Scripted::keys("    /start\n!"),
+        )?;
+
+        assert_eq!(out.matches("\x1b[?1049h").count(), 1, "{out:?}");
+        assert_eq!(out.matches("\x1b[?1049l").count(), 1, "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn system_in_pty() -> Result<(), Box<dyn Error>> {
+        let mut cache = SystemCacheEntry::default();
+        System::new(
+            "[ -t 0 ] && [ -t 1 ] && printf tty".into(),
+            0,
+            SystemOptions::default(),
+        )
+        .capture(None, &mut cache)?;
+        assert_eq!(cache.output(), Some(&b"tty"[..]));
+        assert!(cache.requires_newline());
+
+        System::new("echo foo; echo bar >&2".into(), 0, SystemOptions::default())
+            .capture(None, &mut cache)?;
+        assert_eq!(cache.output(), Some(&b"foo\r\nbar\r\n"[..]));
+        assert!(!cache.requires_newline());
+
+        System::new("stty size".into(), 0, SystemOptions::default())
+            .capture(Some((100, 30)), &mut cache)?;
+        assert_eq!(cache.output(), Some(&b"30 100\r\n"[..]));
+
+        // Recordings and snapshots run commands at their own size.
+        let lie = lie::test::test_script(
+            true,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.system("stty size");
+            "#,
+        )?;
+        let mut out = vec![];
+        Tale::from(lie).tell(
+            &mut out,
+            &mut Headless::new(Duration::ZERO, Default::default(), (100, 30)),
+        )?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("30 100\r\n"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn system_environment() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let mut cache = SystemCacheEntry::default();
+        System::new(
+            r#"printf "%s %s %s" "$(basename "$PWD")" "$FOO" "${HOME:-none}""#.into(),
+            0,
+            SystemOptions {
+                cwd: Some(dir.path().to_string_lossy().into()),
+                env: vec![("FOO".into(), "bar".into())],
+                clear_env: true,
+                ..Default::default()
+            },
+        )
+        .capture(None, &mut cache)?;
+
+        let expected = format!(
+            "{} bar none",
+            dir.path().file_name().unwrap().to_string_lossy()
+        );
+        assert_eq!(cache.output(), Some(expected.as_bytes()));
+
+        let pagers = r#"printf "%s %s %s" "$PAGER" "$GIT_PAGER" "$MANPAGER""#;
+        System::new(pagers.into(), 0, SystemOptions::default()).capture(None, &mut cache)?;
+        assert_eq!(cache.output(), Some(&b"cat cat cat"[..]));
+
+        System::new(
+            pagers.into(),
+            0,
+            SystemOptions {
+                env: vec![("GIT_PAGER".into(), "less".into())],
+                ..Default::default()
+            },
+        )
+        .capture(None, &mut cache)?;
+        assert_eq!(cache.output(), Some(&b"cat less cat"[..]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn system_interrupted() -> Result<(), Box<dyn Error>> {
+        let start = Instant::now();
+        let mut out = vec![];
+        let mut cache = SystemCacheEntry::default();
+        System::new("sleep 10".into(), 0, SystemOptions::default()).stream(
+            &mut out,
+            &mut Scripted::default().then_ctrl('c'),
+            &mut cache,
+        )?;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(cache.status(), 130);
+
+        Ok(())
+    }
+
+    #[test]
+    fn system_cwd_relative_to_lie() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let demo = dir.path().join("talk").join("demo");
+        std::fs::create_dir_all(&demo)?;
+        let lie_path = dir.path().join("talk").join("lie.rhai");
+        std::fs::write(
+            &lie_path,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.system("pwd -P", #{ cwd: "demo" });
+            "#,
+        )?;
+
+        let lie = lie::read(&lie_path, false, crate::policy::SystemPolicy::Unleashed)?;
+        let mut out = vec![];
+        Tale::from(lie).tell(&mut out, &mut Scripted::keys("    "))?;
+        let out = String::from_utf8(out)?;
+        let expected = demo.canonicalize()?;
+        assert!(out.contains(&*expected.to_string_lossy()), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn system_fallback() -> Result<(), Box<dyn Error>> {
+        let fallback = |cmd: &str, options| -> Result<_, Box<dyn Error>> {
+            let mut out = vec![];
+            let mut cache = SystemCacheEntry::default();
+            System::new(cmd.into(), 0, options).stream(
+                &mut out,
+                &mut Scripted::default(),
+                &mut cache,
+            )?;
+            assert_eq!(cache.output(), Some(&out[..]));
+            Ok(String::from_utf8(out)?)
+        };
+        let offline = || Some(vec!["offline\r\n".into()]);
+
+        let start = Instant::now();
+        let out = fallback(
+            "echo online; sleep 10",
+            SystemOptions {
+                timeout: Some(Duration::from_millis(200)),
+                fallback: offline(),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(out, "offline\r\n");
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let out = fallback(
+            "echo online; sleep 10",
+            SystemOptions {
+                timeout: Some(Duration::from_millis(200)),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(out, "online\r\n");
+
+        let out = fallback(
+            "echo online; exit 1",
+            SystemOptions {
+                fallback: offline(),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(out, "offline\r\n");
+
+        let out = fallback(
+            "echo online; exit 1",
+            SystemOptions {
+                expected_status: Some(1),
+                fallback: offline(),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(out, "online\r\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn interactive_system() -> Result<(), Box<dyn Error>> {
+        let mut out = vec![];
+        let mut cache = SystemCacheEntry::default();
+        System::new(
+            "head -n1".into(),
+            0,
+            SystemOptions {
+                interactive: true,
+                ..Default::default()
+            },
+        )
+        .interact(&mut out, &mut Scripted::keys("hello\n"), &mut cache)?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(out.matches("hello\r\n").count(), 2, "{out:?}");
+
+        let lie = lie::test::test_script(
+            true,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.system("echo $((6 * 7))", #{ interactive: true });
+            "#,
+        )?;
+        let mut out = vec![];
+        Tale::from(lie).tell(
+            &mut out,
+            &mut Headless::new(Duration::ZERO, Default::default(), (80, 24)),
+        )?;
+        let out = String::from_utf8(out)?;
+        assert!(!out.contains("42"), "{out:?}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn replay_cache() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            true,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.system("echo foo");
+            "#,
+        )?;
+
+        let prepared = Tale::from(lie.clone()).prepare()?;
+        assert_eq!(prepared.get(0, "echo foo").unwrap().output(), b"foo\r\n");
+
+        let mut cache = Cache::default();
+        cache.push("echo foo", b"cached\r\n", false, 0);
+        let mut out = vec![];
+        Tale::from(lie.clone())
+            .with_cache(&cache)?
+            .tell(&mut out, &mut Scripted::keys("   "))?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("cached\r\n"), "{out:?}");
+
+        let mut stale = Cache::default();
+        stale.push("echo bar", b"bar\r\n", false, 0);
+        assert!(Tale::from(lie).with_cache(&stale).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn prefetch() -> Result<(), Box<dyn Error>> {
+        let lie = lie::test::test_script(
+            true,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.system("sleep 0.2; echo foo");
+                lie.pause();
+                lie.system("printf 'bar\nbaz'");
+            "#,
+        )?;
+
+        for line_delay in [Duration::ZERO, Duration::from_millis(1)] {
+            let mut out = vec![];
+            Tale::from(lie.clone())
+                .with_prefetch(Some(line_delay))
+                .tell(&mut out, &mut Scripted::keys("      "))?;
+            let out = String::from_utf8(out)?;
+            assert!(out.contains("foo\r\n"), "{out:?}");
+            assert!(out.contains("bar\r\nbaz\r\n"), "{out:?}");
         }
+
+        Ok(())
+    }
+
+    #[test]
+    fn bounds() -> Result<(), Box<dyn Error>> {
+        let script = r#"
+            lie.look(#{ speed: 0.0 });
+            lie.cd("/tmp");
+            lie.tag("foo");
+            lie.run("echo foo");
+            lie.tag("bar");
+            lie.run("echo bar");
+        "#;
+
+        let mut out = vec![];
+        Tale::from(lie::test::test_script(false, script)?)
+            .with_bounds(Some("foo"), Some("bar"))?
+            .tell(&mut out, &mut Scripted::keys("  "))?;
+        let out = String::from_utf8(out)?;
+        assert!(!out.contains("cd /tmp"), "{out:?}");
+        assert!(out.contains("/tmp\x1b[0m$ "), "{out:?}");
+        assert!(out.contains("echo foo"), "{out:?}");
+        assert!(!out.contains("echo bar"), "{out:?}");
+
+        let mut out = vec![];
+        Tale::from(lie::test::test_script(
+            false,
+            r#"
+                lie.look(#{ speed: 0.0 });
+                lie.screen(|lie| {
+                    lie.run("echo foo");
+                    lie.tag("end");
+                    lie.run("echo bar");
+                });
+            "#,
+        )?)
+        .with_bounds(None, Some("end"))?
+        .tell(&mut out, &mut Scripted::keys("  "))?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(out.matches("\x1b[?1049h").count(), 1, "{out:?}");
+        assert_eq!(out.matches("\x1b[?1049l").count(), 1, "{out:?}");
+
+        // Going back redraws from the start tag, not the very beginning.
+        let mut out = vec![];
+        Tale::from(lie::test::test_script(false, script)?)
+            .with_bounds(Some("foo"), None)?
+            .tell(&mut out, &mut Scripted::keys("  b      "))?;
+        let out = String::from_utf8(out)?;
+        assert!(!out.contains("cd /tmp"), "{out:?}");
+        assert_eq!(out.matches("echo foo").count(), 2, "{out:?}");
+
+        assert_eq!(
+            Tale::from(lie::test::test_script(false, script)?)
+                .with_bounds(Some("baz"), None)
+                .unwrap_err()
+                .to_string(),
+            "unknown tag 'baz', expected one of: foo, bar"
+        );
+        assert_eq!(
+            Tale::from(lie::test::test_script(false, script)?)
+                .with_bounds(Some("bar"), Some("foo"))
+                .unwrap_err()
+                .to_string(),
+            "tag 'foo' does not come after tag 'bar'"
+        );
+
+        Ok(())
     }
 }
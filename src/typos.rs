@@ -0,0 +1,79 @@
+use phf::phf_map;
+use rand::{seq::SliceRandom, Rng};
+
+/// The keys surrounding each key on a QWERTY keyboard, which are the easiest to hit by mistake.
+static NEIGHBOURS: phf::Map<char, &'static str> = phf_map! {
+    '1' => "2q",
+    '2' => "13qw",
+    '3' => "24we",
+    '4' => "35er",
+    '5' => "46rt",
+    '6' => "57ty",
+    '7' => "68yu",
+    '8' => "79ui",
+    '9' => "80io",
+    '0' => "9-op",
+    '-' => "0=p[",
+    '=' => "-[]",
+    'q' => "12wa",
+    'w' => "23qeas",
+    'e' => "34wrsd",
+    'r' => "45etdf",
+    't' => "56ryfg",
+    'y' => "67tugh",
+    'u' => "78yihj",
+    'i' => "89uojk",
+    'o' => "90ipkl",
+    'p' => "0-o[l;",
+    '[' => "-=p];",
+    ']' => "=['",
+    'a' => "qwsz",
+    's' => "weadzx",
+    'd' => "ersfxc",
+    'f' => "rtdgcv",
+    'g' => "tyfhvb",
+    'h' => "yugjbn",
+    'j' => "uihknm",
+    'k' => "iojlm,",
+    'l' => "opk;,.",
+    ';' => "p[l'./",
+    '\'' => "[];/",
+    'z' => "asx",
+    'x' => "sdzc",
+    'c' => "dfxv",
+    'v' => "fgcb",
+    'b' => "ghvn",
+    'n' => "hjbm",
+    'm' => "jkn,",
+    ',' => "klm.",
+    '.' => "l;,/",
+    '/' => ";'.",
+};
+
+/// A key which might plausibly be hit instead of `c`, keeping its case, if `c` is on the
+/// keyboard.
+pub fn near_miss<R: Rng>(c: char, rng: &mut R) -> Option<char> {
+    let neighbours: Vec<_> = NEIGHBOURS.get(&c.to_ascii_lowercase())?.chars().collect();
+    let miss = *neighbours.choose(rng)?;
+    Some(if c.is_ascii_uppercase() {
+        miss.to_ascii_uppercase()
+    } else {
+        miss
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn near_misses() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            assert!("qwsz".contains(near_miss('a', &mut rng).unwrap()));
+            assert!("QWSZ".contains(near_miss('A', &mut rng).unwrap()));
+        }
+        assert_eq!(near_miss(' ', &mut rng), None);
+        assert_eq!(near_miss('é', &mut rng), None);
+    }
+}